@@ -0,0 +1,184 @@
+//! Derive macros for the GenesisDB client SDK
+//!
+//! Use them through the `derive` feature of `genesisdb`, which re-exports
+//! them next to the traits they implement.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Implement `DomainEvent` and `EventSet` for a struct
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize, DomainEvent)]
+/// #[domain_event(type = "io.genesisdb.app.user-created", source = "io.genesisdb.app")]
+/// struct UserCreated {
+///     name: String,
+/// }
+/// ```
+#[proc_macro_derive(DomainEvent, attributes(domain_event))]
+pub fn derive_domain_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    domain_event(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implement `EventSet` for an enum of domain events
+///
+/// Every variant wraps a single `DomainEvent`. A variant wrapping a
+/// `CloudEvent` and marked with `#[domain_event(unknown)]` receives events of
+/// any other type.
+#[proc_macro_derive(EventSet, attributes(domain_event))]
+pub fn derive_event_set(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    event_set(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn domain_event(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    if !matches!(input.data, Data::Struct(_)) {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "DomainEvent can only be derived for structs, use EventSet for enums",
+        ));
+    }
+
+    let mut event_type = None;
+    let mut source = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("domain_event")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("type") {
+                event_type = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else if meta.path.is_ident("source") {
+                source = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `type` or `source`"))
+            }
+        })?;
+    }
+
+    let missing = |name: &str| {
+        syn::Error::new_spanned(
+            &input.ident,
+            format!("missing `#[domain_event({} = \"...\")]`", name),
+        )
+    };
+    let event_type = event_type.ok_or_else(|| missing("type"))?;
+    let source = source.ok_or_else(|| missing("source"))?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::genesisdb_io_client::DomainEvent for #name #ty_generics #where_clause {
+            const EVENT_TYPE: &'static str = #event_type;
+            const SOURCE: &'static str = #source;
+        }
+
+        impl #impl_generics ::genesisdb_io_client::EventSet for #name #ty_generics #where_clause {
+            fn decode(event: &::genesisdb_io_client::CloudEvent) -> ::genesisdb_io_client::Result<Self> {
+                match <Self as ::genesisdb_io_client::DomainEvent>::from_cloud_event(event)? {
+                    Some(decoded) => Ok(decoded),
+                    None => Err(::genesisdb_io_client::__private::unknown_event_type(event)),
+                }
+            }
+
+            fn encode(&self, subject: &str) -> ::genesisdb_io_client::Result<::genesisdb_io_client::CommitEvent> {
+                ::genesisdb_io_client::DomainEvent::to_commit_event(self, subject)
+            }
+        }
+    })
+}
+
+fn event_set(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "EventSet can only be derived for enums",
+        ));
+    };
+
+    let mut known = Vec::new();
+    let mut unknown = None;
+    for variant in &data.variants {
+        let ty = match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "EventSet variants must wrap exactly one event",
+                ))
+            }
+        };
+
+        let mut is_unknown = false;
+        for attr in variant.attrs.iter().filter(|a| a.path().is_ident("domain_event")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("unknown") {
+                    is_unknown = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `unknown`"))
+                }
+            })?;
+        }
+
+        if !is_unknown {
+            known.push((&variant.ident, ty));
+        } else if unknown.is_some() {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "only one variant can be marked `#[domain_event(unknown)]`",
+            ));
+        } else {
+            unknown = Some(&variant.ident);
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let decode_known = known.iter().map(|(variant, ty)| {
+        quote! {
+            if let Some(decoded) = <#ty as ::genesisdb_io_client::DomainEvent>::from_cloud_event(event)? {
+                return Ok(Self::#variant(decoded));
+            }
+        }
+    });
+    let decode_fallback = match unknown {
+        Some(variant) => quote! { Ok(Self::#variant(event.clone())) },
+        None => quote! { Err(::genesisdb_io_client::__private::unknown_event_type(event)) },
+    };
+
+    let encode_known = known.iter().map(|(variant, _)| {
+        quote! {
+            Self::#variant(inner) => ::genesisdb_io_client::DomainEvent::to_commit_event(inner, subject),
+        }
+    });
+    let encode_unknown = unknown.map(|variant| {
+        quote! {
+            Self::#variant(event) => Ok(::genesisdb_io_client::__private::encode_unknown(event, subject)),
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::genesisdb_io_client::EventSet for #name #ty_generics #where_clause {
+            fn decode(event: &::genesisdb_io_client::CloudEvent) -> ::genesisdb_io_client::Result<Self> {
+                #(#decode_known)*
+                #decode_fallback
+            }
+
+            fn encode(&self, subject: &str) -> ::genesisdb_io_client::Result<::genesisdb_io_client::CommitEvent> {
+                match self {
+                    #(#encode_known)*
+                    #encode_unknown
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,228 @@
+//! Aggregates and repositories on top of streaming and committing events
+
+use crate::client::Client;
+use crate::domain::EventSet;
+use crate::error::{Error, Result};
+use crate::gdbql::{count, field, Query};
+use crate::types::{CommitResult, Precondition};
+use futures::stream::TryStreamExt;
+use std::fmt;
+use std::marker::PhantomData;
+use thiserror::Error;
+
+/// State rebuilt from the events of a subject and its child subjects
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Default)]
+/// struct Order {
+///     placed: bool,
+/// }
+///
+/// impl Aggregate for Order {
+///     type Event = OrderEvent;
+///     type Command = OrderCommand;
+///     type Error = OrderError;
+///
+///     fn subject(id: &str) -> String {
+///         format!("/order/{}", id)
+///     }
+///
+///     fn apply(&mut self, event: &OrderEvent) {
+///         if let OrderEvent::Placed(_) = event {
+///             self.placed = true;
+///         }
+///     }
+///
+///     fn handle(&self, command: &OrderCommand) -> Result<Vec<OrderEvent>, OrderError> {
+///         match command {
+///             OrderCommand::Place if self.placed => Err(OrderError::AlreadyPlaced),
+///             OrderCommand::Place => Ok(vec![OrderEvent::Placed(OrderPlaced {})]),
+///         }
+///     }
+/// }
+/// ```
+pub trait Aggregate: Default {
+    /// Events the aggregate is built from
+    type Event: EventSet;
+
+    /// Commands the aggregate handles
+    type Command;
+
+    /// Error returned when a command is rejected
+    type Error;
+
+    /// Subject holding the events of the aggregate with the given id
+    fn subject(id: &str) -> String;
+
+    /// Apply an event to the state
+    fn apply(&mut self, event: &Self::Event);
+
+    /// Decide which events a command results in, without changing the state
+    fn handle(&self, command: &Self::Command) -> std::result::Result<Vec<Self::Event>, Self::Error>;
+}
+
+/// An aggregate loaded from GenesisDB
+#[derive(Debug, Clone)]
+pub struct Loaded<A> {
+    /// Id the aggregate was loaded for
+    pub id: String,
+    /// State after applying all events of the subject and its child subjects
+    pub state: A,
+    /// Id of the last event applied, `None` if the subject has no events
+    pub last_event_id: Option<String>,
+    /// Number of events applied
+    pub version: u64,
+}
+
+/// Error returned by [`Repository::execute`]
+#[derive(Error, Debug)]
+pub enum CommandError<E> {
+    /// The aggregate rejected the command
+    #[error("Command rejected: {0}")]
+    Rejected(E),
+
+    /// Loading or committing failed
+    #[error(transparent)]
+    Client(#[from] Error),
+}
+
+/// Loads aggregates and commits the events of their commands
+///
+/// Commits are guarded by an optimistic-concurrency precondition: they only
+/// succeed if the subject and its child subjects still hold as many events
+/// as when the aggregate was loaded. Writes to child subjects count as
+/// conflicts, as they are applied to the state too. On conflict the
+/// aggregate is reloaded and the command handled again.
+pub struct Repository<A> {
+    client: Client,
+    max_conflict_retries: u32,
+    aggregate: PhantomData<fn() -> A>,
+}
+
+impl<A> Clone for Repository<A> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            max_conflict_retries: self.max_conflict_retries,
+            aggregate: PhantomData,
+        }
+    }
+}
+
+impl<A> fmt::Debug for Repository<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Repository")
+            .field("client", &self.client)
+            .field("max_conflict_retries", &self.max_conflict_retries)
+            .finish()
+    }
+}
+
+impl<A: Aggregate> Repository<A> {
+    /// Create a repository that retries a command up to 3 times on conflict
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            max_conflict_retries: 3,
+            aggregate: PhantomData,
+        }
+    }
+
+    /// Set how often a command is handled again after a conflicting commit
+    pub fn max_conflict_retries(mut self, retries: u32) -> Self {
+        self.max_conflict_retries = retries;
+        self
+    }
+
+    /// Load an aggregate by applying all events of its subject and its child subjects
+    pub async fn load(&self, id: &str) -> Result<Loaded<A>> {
+        let subject = A::subject(id);
+        let mut events = self.client.stream_events_iter(&subject, None).await?;
+        let mut state = A::default();
+        let mut last_event_id = None;
+        let mut version = 0;
+
+        while let Some(event) = events.try_next().await? {
+            state.apply(&A::Event::decode(&event)?);
+            version += 1;
+            last_event_id = Some(event.id);
+        }
+
+        Ok(Loaded {
+            id: id.to_string(),
+            state,
+            last_event_id,
+            version,
+        })
+    }
+
+    /// Commit events for a loaded aggregate
+    ///
+    /// Fails with [`Error::PreconditionFailed`] if events have been written to
+    /// the subject or its child subjects since the aggregate was loaded.
+    pub async fn commit(&self, loaded: &Loaded<A>, events: &[A::Event]) -> Result<CommitResult> {
+        if events.is_empty() {
+            return Ok(CommitResult::default());
+        }
+
+        let subject = A::subject(&loaded.id);
+        let precondition = Precondition::is_query_result_true(version_query(&subject, loaded.version));
+
+        let events = events
+            .iter()
+            .map(|event| event.encode(&subject))
+            .collect::<Result<Vec<_>>>()?;
+
+        // The guard fails once the events are stored, so retrying cannot write them twice
+        self.client.commit_events_with_retry(events, vec![precondition]).await
+    }
+
+    /// Load an aggregate, handle a command and commit the resulting events
+    ///
+    /// Returns the committed events.
+    pub async fn execute(
+        &self,
+        id: &str,
+        command: &A::Command,
+    ) -> std::result::Result<Vec<A::Event>, CommandError<A::Error>> {
+        let mut conflicts = 0;
+        loop {
+            let loaded = self.load(id).await?;
+            let events = loaded.state.handle(command).map_err(CommandError::Rejected)?;
+
+            match self.commit(&loaded, &events).await {
+                Ok(_) => return Ok(events),
+                Err(Error::PreconditionFailed { .. }) if conflicts < self.max_conflict_retries => {
+                    conflicts += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Query that is true while the subject and its child subjects hold exactly `version` events
+///
+/// Counting instead of comparing the latest event id keeps the guard
+/// independent of time ordering, which is ambiguous for events committed in
+/// the same batch.
+fn version_query(subject: &str, version: u64) -> Query {
+    Query::stream("e")
+        .filter(field("e.subject").under(subject))
+        .project(count().eq(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_query_escapes_subject() {
+        assert_eq!(
+            version_query("/order/o'1", 3).to_string(),
+            "STREAM e FROM events WHERE e.subject UNDER '/order/o\\'1' MAP COUNT() == 3"
+        );
+    }
+}
@@ -0,0 +1,516 @@
+//! Export and import of subjects as NDJSON archives
+
+use crate::bulk::{BulkProgress, ProgressCallback};
+use crate::checkpoint::write_durably;
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::idempotency::IdempotencyKey;
+use crate::types::{CloudEvent, CommitEvent};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
+use uuid::Uuid;
+
+/// Version of the archive format written by this client
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Compression of the events file of an archive
+///
+/// Gzip needs the `gzip` feature and zstd the `zstd` feature; using them
+/// without returns [`Error::InvalidConfig`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    fn check_supported(self) -> Result<()> {
+        let feature = match self {
+            Compression::Gzip if !cfg!(feature = "gzip") => "gzip",
+            Compression::Zstd if !cfg!(feature = "zstd") => "zstd",
+            _ => return Ok(()),
+        };
+        Err(Error::InvalidConfig(format!(
+            "{} compression requires the `{}` feature",
+            self, feature
+        )))
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        })
+    }
+}
+
+impl FromStr for Compression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            other => Err(Error::InvalidConfig(format!("unknown compression: {}", other))),
+        }
+    }
+}
+
+/// Description of an archive, stored next to its events file
+///
+/// The manifest is written after all events, so an export that was
+/// interrupted leaves no manifest behind and cannot be imported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Version of the archive format
+    pub version: u32,
+    /// Subject the events were exported from
+    pub subject: String,
+    /// Number of events in the archive
+    pub count: usize,
+    /// Id of the first event, `None` for an empty archive
+    pub first_event_id: Option<String>,
+    /// Id of the last event, `None` for an empty archive
+    pub last_event_id: Option<String>,
+    /// SHA-256 of the uncompressed events file, as `sha256:<hex>`
+    pub checksum: String,
+    /// Compression of the events file
+    pub compression: Compression,
+    /// Time the export finished
+    pub exported_at: DateTime<Utc>,
+}
+
+impl ArchiveManifest {
+    /// Path of the manifest belonging to the events file at `archive`
+    pub fn path_for(archive: impl AsRef<Path>) -> PathBuf {
+        let mut path = archive.as_ref().to_path_buf().into_os_string();
+        path.push(".manifest.json");
+        path.into()
+    }
+
+    /// Read the manifest of the archive at `archive`
+    pub async fn read(archive: impl AsRef<Path>) -> Result<Self> {
+        let path = Self::path_for(archive);
+        let manifest: Self = match tokio::fs::read(&path).await {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::InvalidArchive(format!(
+                    "missing manifest {}",
+                    path.display()
+                )))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if manifest.version != ARCHIVE_VERSION {
+            return Err(Error::InvalidArchive(format!(
+                "unsupported archive version {}",
+                manifest.version
+            )));
+        }
+        Ok(manifest)
+    }
+}
+
+/// Options for exporting a subject
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Compression of the events file
+    pub compression: Compression,
+}
+
+/// Options for importing an archive
+///
+/// The events are committed in batches of `batch_size`, each batch
+/// atomically. The progress callback is called after every batch, with
+/// positions and counts relative to the start of the archive, so skipped
+/// events count as committed.
+///
+/// # Example
+///
+/// ```
+/// # use genesisdb_io_client::ImportOptions;
+/// let options = ImportOptions {
+///     offset: 2000,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct ImportOptions {
+    /// Number of events committed per request
+    pub batch_size: usize,
+    /// Number of events at the start of the archive to skip, to resume an import
+    pub offset: usize,
+    /// Read and verify the archive without committing anything
+    pub dry_run: bool,
+    /// Called after every committed batch
+    pub on_progress: Option<ProgressCallback>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 1000,
+            offset: 0,
+            dry_run: false,
+            on_progress: None,
+        }
+    }
+}
+
+impl fmt::Debug for ImportOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImportOptions")
+            .field("batch_size", &self.batch_size)
+            .field("offset", &self.offset)
+            .field("dry_run", &self.dry_run)
+            .field("on_progress", &self.on_progress.is_some())
+            .finish()
+    }
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportSummary {
+    /// Number of events skipped because of [`ImportOptions::offset`]
+    pub skipped: usize,
+    /// Number of events committed, or that would be committed on a dry run
+    pub imported: usize,
+    /// Whether nothing was committed
+    pub dry_run: bool,
+}
+
+fn checksum(hasher: Sha256) -> String {
+    format!("sha256:{:x}", hasher.finalize())
+}
+
+async fn writer(path: &Path, compression: Compression) -> Result<Box<dyn AsyncWrite + Send + Unpin>> {
+    compression.check_supported()?;
+    let file = BufWriter::new(tokio::fs::File::create(path).await?);
+    Ok(match compression {
+        Compression::None => Box::new(file),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(async_compression::tokio::write::GzipEncoder::new(file)),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(async_compression::tokio::write::ZstdEncoder::new(file)),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("compression support is checked above"),
+    })
+}
+
+async fn reader(path: &Path, compression: Compression) -> Result<Box<dyn AsyncBufRead + Send + Unpin>> {
+    compression.check_supported()?;
+    let file = BufReader::new(tokio::fs::File::open(path).await?);
+    Ok(match compression {
+        Compression::None => Box::new(file),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(BufReader::new(
+            async_compression::tokio::bufread::GzipDecoder::new(file),
+        )),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(BufReader::new(
+            async_compression::tokio::bufread::ZstdDecoder::new(file),
+        )),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("compression support is checked above"),
+    })
+}
+
+/// Lines of an archive's events file, checked against its manifest
+struct ArchiveReader {
+    reader: Box<dyn AsyncBufRead + Send + Unpin>,
+    hasher: Sha256,
+    line: String,
+    position: usize,
+}
+
+impl ArchiveReader {
+    async fn open(path: &Path, manifest: &ArchiveManifest) -> Result<Self> {
+        Ok(Self {
+            reader: reader(path, manifest.compression).await?,
+            hasher: Sha256::new(),
+            line: String::new(),
+            position: 0,
+        })
+    }
+
+    /// Next event and its position in the archive
+    async fn next(&mut self) -> Result<Option<(usize, CloudEvent)>> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line).await? == 0 {
+            return Ok(None);
+        }
+        self.hasher.update(self.line.as_bytes());
+
+        let position = self.position;
+        self.position += 1;
+        let event = serde_json::from_str(&self.line).map_err(|e| {
+            Error::InvalidArchive(format!("event {} is not valid: {}", position, e))
+        })?;
+        Ok(Some((position, event)))
+    }
+
+    /// Check that all events were read and match the manifest
+    fn verify(self, manifest: &ArchiveManifest) -> Result<()> {
+        if self.position != manifest.count {
+            return Err(Error::InvalidArchive(format!(
+                "expected {} events, found {}",
+                manifest.count, self.position
+            )));
+        }
+        let checksum = checksum(self.hasher);
+        if checksum != manifest.checksum {
+            return Err(Error::InvalidArchive(format!(
+                "checksum mismatch, expected {}, found {}",
+                manifest.checksum, checksum
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Export the events of a subject and its nested subjects to an archive
+    ///
+    /// Writes one event per line to `path`, compressed as configured, and
+    /// the [`ArchiveManifest`] to [`ArchiveManifest::path_for`]`(path)`.
+    /// Events are streamed to the file as they arrive, so subjects of any
+    /// size can be exported.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::{Client, ExportOptions};
+    /// # async fn example(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// let manifest = client
+    ///     .export_events("/tenant/42", "tenant-42.ndjson", &ExportOptions::default())
+    ///     .await?;
+    /// println!("exported {} events", manifest.count);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn export_events(
+        &self,
+        subject: &str,
+        path: impl AsRef<Path>,
+        options: &ExportOptions,
+    ) -> Result<ArchiveManifest> {
+        let path = path.as_ref();
+        options.compression.check_supported()?;
+
+        let mut events = self.stream_events_iter(subject, None).await?;
+        let mut writer = writer(path, options.compression).await?;
+        let mut hasher = Sha256::new();
+        let mut count = 0;
+        let mut first_event_id = None;
+        let mut last_event_id = None;
+
+        while let Some(event) = events.next().await {
+            let event = event?;
+            let mut line = serde_json::to_vec(&event)?;
+            line.push(b'\n');
+            hasher.update(&line);
+            writer.write_all(&line).await?;
+
+            count += 1;
+            first_event_id.get_or_insert_with(|| event.id.clone());
+            last_event_id = Some(event.id);
+        }
+        writer.shutdown().await?;
+
+        let manifest = ArchiveManifest {
+            version: ARCHIVE_VERSION,
+            subject: subject.to_string(),
+            count,
+            first_event_id,
+            last_event_id,
+            checksum: checksum(hasher),
+            compression: options.compression,
+            exported_at: Utc::now(),
+        };
+        // The manifest vouches for the events file, so both must be on disk before it appears
+        tokio::fs::File::open(path).await?.sync_all().await?;
+        write_durably(&ArchiveManifest::path_for(path), &serde_json::to_vec_pretty(&manifest)?).await?;
+
+        Ok(manifest)
+    }
+
+    /// Commit the events of an archive, in their original order
+    ///
+    /// The archive is verified against its manifest before anything is
+    /// committed. Events keep their source, subject, type and data; they
+    /// get new times and ids derived from the manifest checksum and their
+    /// position in the archive.
+    ///
+    /// If a batch fails, [`Error::ImportFailed`] reports the offset of the
+    /// first event that was not committed. Pass it as
+    /// [`ImportOptions::offset`] to continue the import, with any
+    /// `batch_size`. Events after the offset that were stored although
+    /// their commit failed are found by their ids and skipped, and each
+    /// batch is committed with
+    /// [`commit_events_idempotent`](Client::commit_events_idempotent), so no
+    /// event is committed twice.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::{Client, Error, ImportOptions};
+    /// # async fn example(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut options = ImportOptions::default();
+    ///
+    /// if let Err(Error::ImportFailed { offset, .. }) = client.import_events("tenant-42.ndjson", &options).await {
+    ///     options.offset = offset;
+    ///     client.import_events("tenant-42.ndjson", &options).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn import_events(
+        &self,
+        path: impl AsRef<Path>,
+        options: &ImportOptions,
+    ) -> Result<ImportSummary> {
+        let path = path.as_ref();
+        if options.batch_size == 0 {
+            return Err(Error::InvalidConfig(
+                "batch_size must be greater than 0".to_string(),
+            ));
+        }
+
+        let manifest = ArchiveManifest::read(path).await?;
+        manifest.compression.check_supported()?;
+        if options.offset > manifest.count {
+            return Err(Error::InvalidConfig(format!(
+                "offset {} is beyond the {} events of the archive",
+                options.offset, manifest.count
+            )));
+        }
+
+        // Read the whole archive once, so a corrupt file is rejected before anything is committed
+        let mut archive = ArchiveReader::open(path, &manifest).await?;
+        while archive.next().await?.is_some() {}
+        archive.verify(&manifest)?;
+
+        let summary = ImportSummary {
+            skipped: options.offset,
+            imported: manifest.count - options.offset,
+            dry_run: options.dry_run,
+        };
+        if options.dry_run {
+            return Ok(summary);
+        }
+
+        let archive_key = IdempotencyKey::from(manifest.checksum.as_str());
+        let mut archive = ArchiveReader::open(path, &manifest).await?;
+        let mut batch = Vec::with_capacity(options.batch_size);
+        let mut start = options.offset;
+        // An earlier attempt may have stored events after the offset although its commit failed
+        let mut skip_stored = true;
+
+        loop {
+            let next = archive.next().await?;
+            let done = next.is_none();
+            if let Some((position, event)) = next {
+                if position < options.offset {
+                    continue;
+                }
+                let mut event = CommitEvent::from(event);
+                event.id = Some(archive_key.event_id(position));
+                batch.push(event);
+                if batch.len() < options.batch_size {
+                    continue;
+                }
+            }
+
+            if !batch.is_empty() {
+                let end = start + batch.len();
+                let mut events = std::mem::take(&mut batch);
+                if skip_stored {
+                    let stored = self
+                        .stored_prefix(&events)
+                        .await
+                        .map_err(|error| import_failed(start, error))?;
+                    skip_stored = stored == events.len();
+                    events.drain(..stored);
+                }
+                if !events.is_empty() {
+                    let first = end - events.len();
+                    let key = IdempotencyKey::from(format!("{}/{}", manifest.checksum, first));
+                    self.commit_events_idempotent(events, None, &key)
+                        .await
+                        .map_err(|error| import_failed(first, error))?;
+                }
+                if let Some(on_progress) = &options.on_progress {
+                    on_progress(&BulkProgress {
+                        chunk: start..end,
+                        committed: end,
+                        total: manifest.count,
+                    });
+                }
+                start = end;
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Number of events at the start of the batch that are already stored
+    async fn stored_prefix(&self, events: &[CommitEvent]) -> Result<usize> {
+        let mut subjects: Vec<String> = events.iter().map(|event| event.subject.clone()).collect();
+        subjects.sort();
+        subjects.dedup();
+        let ids: Vec<Uuid> = events.iter().filter_map(|event| event.id).collect();
+
+        let stored = self.stored_events(&subjects, &ids).await?;
+        Ok(stored.iter().take_while(|event| event.is_some()).count())
+    }
+}
+
+fn import_failed(offset: usize, error: Error) -> Error {
+    Error::ImportFailed {
+        offset,
+        source: Box::new(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_path() {
+        assert_eq!(
+            ArchiveManifest::path_for("exports/tenant.ndjson.gz"),
+            PathBuf::from("exports/tenant.ndjson.gz.manifest.json")
+        );
+    }
+
+    #[test]
+    fn test_compression_names() {
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            assert_eq!(compression.to_string().parse::<Compression>().unwrap(), compression);
+            assert_eq!(
+                serde_json::to_value(compression).unwrap(),
+                serde_json::json!(compression.to_string())
+            );
+        }
+        assert!("brotli".parse::<Compression>().is_err());
+    }
+}
@@ -0,0 +1,476 @@
+//! Structured audit reports
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::types::parse_time;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Outcome of an audit or of one of its checks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditStatus {
+    Passed,
+    Failed,
+    /// The response did not say
+    #[default]
+    Unknown,
+}
+
+impl AuditStatus {
+    /// Status named by a word such as `ok`, `passed`, `failed` or `invalid`
+    fn from_word(word: &str) -> Option<Self> {
+        let word = word
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_ascii_lowercase();
+        match word.as_str() {
+            "ok" | "pass" | "passed" | "success" | "successful" | "succeeded" | "valid"
+            | "verified" | "true" | "healthy" => Some(AuditStatus::Passed),
+            "fail" | "failed" | "failure" | "error" | "invalid" | "corrupt" | "corrupted"
+            | "mismatch" | "false" | "unhealthy" => Some(AuditStatus::Failed),
+            _ => None,
+        }
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(true) => Some(AuditStatus::Passed),
+            Value::Bool(false) => Some(AuditStatus::Failed),
+            Value::String(s) => Self::from_word(s),
+            _ => None,
+        }
+    }
+
+    /// Combine two statuses, a failure wins over everything else
+    fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (AuditStatus::Failed, _) | (_, AuditStatus::Failed) => AuditStatus::Failed,
+            (AuditStatus::Unknown, status) | (status, AuditStatus::Unknown) => status,
+            _ => AuditStatus::Passed,
+        }
+    }
+}
+
+/// One check of an audit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditCheck {
+    /// Name of the check as reported by the server
+    pub name: String,
+    pub status: AuditStatus,
+    /// Explanation given by the server, if any
+    pub message: Option<String>,
+    /// Number of items the check covered, if reported
+    pub count: Option<u64>,
+}
+
+/// Audit of a GenesisDB store, parsed from the response of `/status/audit`
+///
+/// Parsing is lenient: JSON and `key: value` text responses are understood,
+/// and anything that cannot be classified is only kept in [`raw`](Self::raw).
+///
+/// # Example
+///
+/// ```
+/// # use genesisdb_io_client::{AuditReport, AuditStatus};
+/// let report = AuditReport::parse(
+///     "Audit successful\nevents: 42\nhash chain: ok\nlast hash: 9f86d081884c\n",
+/// );
+/// assert_eq!(report.status, AuditStatus::Passed);
+/// assert_eq!(report.counts["events"], 42);
+/// assert_eq!(report.hashes["last hash"], "9f86d081884c");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditReport {
+    /// Overall status, failed if any check failed
+    pub status: AuditStatus,
+    /// Individual checks, in the order reported
+    pub checks: Vec<AuditCheck>,
+    /// Reported counts by name, e.g. `events`
+    pub counts: BTreeMap<String, u64>,
+    /// Reported hashes and checksums by name
+    pub hashes: BTreeMap<String, String>,
+    /// Reported points in time by name
+    pub timestamps: BTreeMap<String, DateTime<Utc>>,
+    /// The response as received
+    pub raw: String,
+}
+
+impl AuditReport {
+    /// Parse an audit response, JSON or text
+    pub fn parse(raw: &str) -> Self {
+        let mut report = AuditReport {
+            status: AuditStatus::Unknown,
+            checks: Vec::new(),
+            counts: BTreeMap::new(),
+            hashes: BTreeMap::new(),
+            timestamps: BTreeMap::new(),
+            raw: raw.to_string(),
+        };
+
+        match serde_json::from_str::<Value>(raw) {
+            Ok(Value::Object(fields)) => report.read_object(&fields),
+            _ => raw.lines().for_each(|line| report.read_line(line)),
+        }
+
+        report.status = report
+            .checks
+            .iter()
+            .fold(report.status, |status, check| status.and(check.status));
+        report
+    }
+
+    /// Whether the audit reported a failure
+    pub fn is_failed(&self) -> bool {
+        self.status == AuditStatus::Failed
+    }
+
+    /// Checks that failed
+    pub fn failed_checks(&self) -> impl Iterator<Item = &AuditCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == AuditStatus::Failed)
+    }
+
+    /// The report, or [`Error::AuditFailed`] unless the audit passed
+    ///
+    /// An audit whose status cannot be determined is treated as failed, so
+    /// a change of the response format does not go unnoticed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::Client;
+    /// # async fn example(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// let report = client.audit_report().await?.ensure_passed()?;
+    /// println!("{} checks passed", report.checks.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ensure_passed(self) -> Result<Self> {
+        if self.status == AuditStatus::Passed {
+            Ok(self)
+        } else {
+            Err(Error::AuditFailed(Box::new(self)))
+        }
+    }
+
+    /// Names of the failed checks, for error messages
+    pub(crate) fn failure_summary(&self) -> String {
+        let names: Vec<&str> = self.failed_checks().map(|check| check.name.as_str()).collect();
+        if self.status == AuditStatus::Unknown {
+            "the result could not be determined".to_string()
+        } else if names.is_empty() {
+            "the server reported a failure".to_string()
+        } else {
+            names.join(", ")
+        }
+    }
+
+    fn read_object(&mut self, fields: &Map<String, Value>) {
+        for (key, value) in fields {
+            match (key.as_str(), value) {
+                ("status" | "result" | "success" | "ok" | "passed" | "valid", _) => {
+                    if let Some(status) = AuditStatus::from_value(value) {
+                        self.status = self.status.and(status);
+                    }
+                }
+                ("checks", Value::Array(checks)) => {
+                    for check in checks {
+                        if let Some(check) = check_from_value(None, check) {
+                            self.checks.push(check);
+                        }
+                    }
+                }
+                ("checks", Value::Object(checks)) => {
+                    for (name, check) in checks {
+                        if let Some(check) = check_from_value(Some(name), check) {
+                            self.checks.push(check);
+                        }
+                    }
+                }
+                (_, Value::Number(n)) => {
+                    if let Some(n) = n.as_u64() {
+                        self.counts.insert(key.clone(), n);
+                    }
+                }
+                (_, Value::String(s)) => self.read_field(key, s),
+                (_, Value::Object(nested)) => {
+                    if let Some(check) = check_from_value(Some(key), value) {
+                        self.checks.push(check);
+                    } else {
+                        self.read_object(nested);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn read_line(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+
+        // Check lists such as `✓ hash chain` or `[FAIL] event order: gap at 17`
+        if let Some((status, rest)) = strip_status_marker(line) {
+            let (name, message) = match rest.split_once(':') {
+                Some((name, message)) => (name.trim(), Some(message.trim().to_string())),
+                None => (rest, None),
+            };
+            self.checks.push(AuditCheck {
+                name: name.to_string(),
+                status,
+                message: message.filter(|m| !m.is_empty()),
+                count: None,
+            });
+            return;
+        }
+
+        match line.split_once(':').or_else(|| line.split_once('=')) {
+            Some((key, value)) if is_field(key) => {
+                self.read_field(key.trim(), value.trim())
+            }
+            _ => self.read_headline(line),
+        }
+    }
+
+    /// Classify the value of a named field
+    fn read_field(&mut self, key: &str, value: &str) {
+        let lower = key.to_ascii_lowercase();
+
+        if let Ok(n) = value.parse::<u64>() {
+            self.counts.insert(key.to_string(), n);
+        } else if let Some(time) = parse_time(value) {
+            self.timestamps.insert(key.to_string(), time);
+        } else if ["hash", "checksum", "digest"].iter().any(|word| lower.contains(word))
+            && AuditStatus::from_word(value).is_none()
+        {
+            self.hashes.insert(key.to_string(), value.to_string());
+        } else if ["status", "result"].contains(&lower.as_str()) || lower == "audit" {
+            if let Some(status) = AuditStatus::from_word(value) {
+                self.status = self.status.and(status);
+            }
+        } else {
+            let (word, message) = match value.split_once(|c: char| c.is_whitespace() || c == ',') {
+                Some((word, message)) => (word, Some(message.trim())),
+                None => (value, None),
+            };
+            if let Some(status) = AuditStatus::from_word(word) {
+                self.checks.push(AuditCheck {
+                    name: key.to_string(),
+                    status,
+                    message: message
+                        .map(|m| m.trim_start_matches(['-', ':', '(']).trim_end_matches(')').trim())
+                        .filter(|m| !m.is_empty())
+                        .map(str::to_string),
+                    count: first_number(value),
+                });
+            }
+        }
+    }
+
+    /// A free text line such as `Audit successful: 42 events verified`
+    fn read_headline(&mut self, line: &str) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        if let Some(status) = words.iter().find_map(|word| AuditStatus::from_word(word)) {
+            self.status = self.status.and(status);
+        }
+        for pair in words.windows(2) {
+            if let Ok(n) = pair[0].parse::<u64>() {
+                let name = pair[1].trim_matches(|c: char| !c.is_alphanumeric());
+                if !name.is_empty() {
+                    self.counts.entry(name.to_string()).or_insert(n);
+                }
+            }
+        }
+    }
+}
+
+/// Whether the part of a text line before a separator names a field
+fn is_field(key: &str) -> bool {
+    let key = key.trim();
+    !key.is_empty()
+        && key.split_whitespace().count() <= 3
+        && AuditStatus::from_word(key.split_whitespace().last().unwrap_or_default()).is_none()
+}
+
+/// Split a leading `✓`, `✗`, `[OK]`, `PASS` or similar marker from a line
+fn strip_status_marker(line: &str) -> Option<(AuditStatus, &str)> {
+    const MARKERS: [(&str, AuditStatus); 6] = [
+        ("✓", AuditStatus::Passed),
+        ("✔", AuditStatus::Passed),
+        ("✗", AuditStatus::Failed),
+        ("✘", AuditStatus::Failed),
+        ("❌", AuditStatus::Failed),
+        ("✅", AuditStatus::Passed),
+    ];
+    if let Some((marker, status)) = MARKERS.iter().find(|(marker, _)| line.starts_with(marker)) {
+        return Some((*status, line[marker.len()..].trim()));
+    }
+
+    let (first, rest) = line.split_once(char::is_whitespace)?;
+    let bracketed = first.starts_with('[') && first.ends_with(']');
+    let upper = first.chars().any(char::is_alphabetic)
+        && first.chars().all(|c| !c.is_lowercase());
+    if bracketed || upper {
+        return AuditStatus::from_word(first).map(|status| (status, rest.trim()));
+    }
+    None
+}
+
+fn check_from_value(name: Option<&str>, value: &Value) -> Option<AuditCheck> {
+    if let Some(status) = AuditStatus::from_value(value) {
+        return Some(AuditCheck {
+            name: name?.to_string(),
+            status,
+            message: None,
+            count: None,
+        });
+    }
+
+    let fields = value.as_object()?;
+    let field = |keys: &[&str]| keys.iter().find_map(|key| fields.get(*key));
+    let status = field(&["status", "result", "passed", "ok", "success", "valid"])
+        .and_then(AuditStatus::from_value)?;
+    let name = field(&["name", "check", "id"])
+        .and_then(Value::as_str)
+        .or(name)?;
+
+    Some(AuditCheck {
+        name: name.to_string(),
+        status,
+        message: field(&["message", "error", "detail", "details"])
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        count: field(&["count", "checked", "events", "total"]).and_then(Value::as_u64),
+    })
+}
+
+fn first_number(text: &str) -> Option<u64> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .find(|part| !part.is_empty())
+        .and_then(|part| part.parse().ok())
+}
+
+impl Client {
+    /// Run an audit and parse its result
+    ///
+    /// The report is returned even if the audit found problems; use
+    /// [`AuditReport::ensure_passed`] to turn those into an error.
+    pub async fn audit_report(&self) -> Result<AuditReport> {
+        Ok(AuditReport::parse(&self.audit().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_text() {
+        let report = AuditReport::parse(concat!(
+            "Audit completed\n",
+            "started: 2026-10-16T08:00:00Z\n",
+            "finished: 2026-10-16 08:00:05\n",
+            "events: 1200\n",
+            "root hash: 3a7bd3e2360a3d29eea436fcfb7e44c735d117c4\n",
+            "✓ event ids unique\n",
+            "[FAIL] hash chain: mismatch at event 17\n",
+            "signatures: ok (1200 verified)\n",
+        ));
+
+        assert_eq!(report.status, AuditStatus::Failed);
+        assert_eq!(report.counts["events"], 1200);
+        assert_eq!(
+            report.hashes["root hash"],
+            "3a7bd3e2360a3d29eea436fcfb7e44c735d117c4"
+        );
+        assert_eq!(
+            report.timestamps["started"],
+            Utc.with_ymd_and_hms(2026, 10, 16, 8, 0, 0).unwrap()
+        );
+        assert_eq!(
+            report.timestamps["finished"],
+            Utc.with_ymd_and_hms(2026, 10, 16, 8, 0, 5).unwrap()
+        );
+        assert_eq!(
+            report.checks,
+            vec![
+                AuditCheck {
+                    name: "event ids unique".to_string(),
+                    status: AuditStatus::Passed,
+                    message: None,
+                    count: None,
+                },
+                AuditCheck {
+                    name: "hash chain".to_string(),
+                    status: AuditStatus::Failed,
+                    message: Some("mismatch at event 17".to_string()),
+                    count: None,
+                },
+                AuditCheck {
+                    name: "signatures".to_string(),
+                    status: AuditStatus::Passed,
+                    message: Some("1200 verified".to_string()),
+                    count: Some(1200),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_headline() {
+        let report = AuditReport::parse("Audit successful: 3 events verified");
+        assert_eq!(report.status, AuditStatus::Passed);
+        assert_eq!(report.counts["events"], 3);
+        assert!(report.checks.is_empty());
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let report = AuditReport::parse(
+            r#"{
+                "status": "ok",
+                "events": 42,
+                "lastHash": "sha256:9f86d081",
+                "completedAt": "2026-10-16T08:00:00+02:00",
+                "checks": [
+                    { "name": "order", "passed": true, "count": 42 },
+                    { "name": "hashes", "status": "failed", "message": "gap" }
+                ]
+            }"#,
+        );
+
+        assert_eq!(report.status, AuditStatus::Failed);
+        assert_eq!(report.counts["events"], 42);
+        assert_eq!(report.hashes["lastHash"], "sha256:9f86d081");
+        assert_eq!(
+            report.timestamps["completedAt"],
+            Utc.with_ymd_and_hms(2026, 10, 16, 6, 0, 0).unwrap()
+        );
+        assert_eq!(report.checks[0].count, Some(42));
+        assert_eq!(
+            report.failed_checks().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+            ["hashes"]
+        );
+        assert!(matches!(
+            report.ensure_passed(),
+            Err(Error::AuditFailed(report)) if report.raw.contains("gap")
+        ));
+    }
+
+    #[test]
+    fn test_unrecognized_text_is_unknown() {
+        let report = AuditReport::parse("nothing to see here");
+        assert_eq!(report.status, AuditStatus::Unknown);
+        assert_eq!(report.raw, "nothing to see here");
+        assert!(!report.is_failed());
+        let error = report.ensure_passed().unwrap_err();
+        assert_eq!(error.to_string(), "Audit failed: the result could not be determined");
+    }
+}
@@ -0,0 +1,270 @@
+//! Command-line tool for operating a GenesisDB store
+//!
+//! Built with the `cli` feature:
+//!
+//! ```text
+//! cargo install genesisdb --features cli
+//! genesisdb --help
+//! ```
+
+mod output;
+
+use clap::{Args, Parser, Subcommand};
+use futures::StreamExt;
+use genesisdb_io_client::{
+    Client, ClientConfig, CommitEvent, Compression, ExportOptions, ImportOptions, StreamOptions,
+    VerifyOptions,
+};
+use output::{Format, Printer};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+type CliResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(name = "genesisdb", version, about = "Operate a GenesisDB store")]
+struct Cli {
+    #[command(flatten)]
+    connection: Connection,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Json, global = true)]
+    output: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Connection settings, falling back to the `GENESISDB_*` environment variables
+#[derive(Args)]
+struct Connection {
+    /// URL of the GenesisDB server
+    #[arg(long, env = "GENESISDB_API_URL", global = true)]
+    url: Option<String>,
+
+    /// API version
+    #[arg(long, env = "GENESISDB_API_VERSION", default_value = "v1", global = true)]
+    api_version: String,
+
+    /// Auth token
+    #[arg(long, env = "GENESISDB_AUTH_TOKEN", hide_env_values = true, global = true)]
+    token: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check that the server is reachable
+    Ping,
+    /// Print the audit report of the server, failing unless the audit passed
+    Audit,
+    /// Print the events of a subject and its nested subjects
+    Stream {
+        subject: String,
+        #[command(flatten)]
+        options: StreamArgs,
+    },
+    /// Print events of a subject as they are committed
+    Observe {
+        subject: String,
+        #[command(flatten)]
+        options: StreamArgs,
+    },
+    /// Commit the events of an NDJSON file in one atomic batch
+    Commit {
+        /// File with one event per line, `-` for stdin
+        #[arg(long, short)]
+        file: PathBuf,
+    },
+    /// Export the events of a subject to an NDJSON archive with manifest
+    Export {
+        subject: String,
+        /// Archive file, the manifest is written next to it
+        #[arg(long, short)]
+        file: PathBuf,
+        /// Compression of the archive: none, gzip or zstd
+        #[arg(long, default_value_t = Compression::None)]
+        compression: Compression,
+    },
+    /// Commit the events of an archive written by `export`
+    Import {
+        /// Archive file
+        #[arg(long, short)]
+        file: PathBuf,
+        /// Verify the archive without committing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip this many events, to resume a failed import
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+        /// Events committed per request
+        #[arg(long, default_value_t = 1000)]
+        batch_size: usize,
+    },
+    /// Check the events of a subject for integrity anomalies
+    Verify {
+        subject: String,
+        #[command(flatten)]
+        options: StreamArgs,
+        /// Require every event to link to the hash of the event before it
+        #[arg(long)]
+        contiguous_chain: bool,
+    },
+    /// Run a GDBQL query
+    Q { query: String },
+    /// Erase the data of a subject
+    Erase { subject: String },
+}
+
+#[derive(Args)]
+struct StreamArgs {
+    /// Only return events after this event id
+    #[arg(long)]
+    lower_bound: Option<String>,
+
+    /// Include the lower bound event itself
+    #[arg(long, requires = "lower_bound")]
+    include_lower_bound: bool,
+
+    /// Only return the latest event of this type
+    #[arg(long)]
+    latest_by_event_type: Option<String>,
+}
+
+impl StreamArgs {
+    fn into_options(self) -> Option<StreamOptions> {
+        let options = StreamOptions {
+            include_lower_bound_event: self.lower_bound.as_ref().map(|_| self.include_lower_bound),
+            lower_bound: self.lower_bound,
+            latest_by_event_type: self.latest_by_event_type,
+        };
+        (options != StreamOptions::default()).then_some(options)
+    }
+}
+
+impl Connection {
+    fn client(self) -> CliResult<Client> {
+        let config = ClientConfig {
+            api_url: self.url.ok_or("missing --url or GENESISDB_API_URL")?,
+            api_version: self.api_version,
+            auth_token: self.token.ok_or("missing --token or GENESISDB_AUTH_TOKEN")?,
+        };
+        Ok(Client::builder(config).transport_from_env()?.build()?)
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> CliResult<()> {
+    let client = cli.connection.client()?;
+    let mut printer = Printer::new(cli.output);
+
+    match cli.command {
+        Command::Ping => printer.text(&client.ping().await?)?,
+        Command::Audit => {
+            let report = client.audit_report().await?;
+            printer.rows(&[&report])?;
+            report.ensure_passed()?;
+        }
+        Command::Stream { subject, options } => {
+            let mut events = client.stream_events_iter(&subject, options.into_options()).await?;
+            while let Some(event) = events.next().await {
+                printer.item(&event?)?;
+            }
+            printer.end_items()?;
+        }
+        Command::Observe { subject, options } => {
+            let mut events = client.observe_events(&subject, options.into_options()).await?;
+            while let Some(event) = events.next().await {
+                printer.row(&event?)?;
+            }
+        }
+        Command::Commit { file } => {
+            let result = client.commit_events(read_events(&file)?, None).await?;
+            printer.rows(&result.events)?;
+        }
+        Command::Export {
+            subject,
+            file,
+            compression,
+        } => {
+            let manifest = client
+                .export_events(&subject, &file, &ExportOptions { compression })
+                .await?;
+            printer.rows(&[manifest])?;
+        }
+        Command::Import {
+            file,
+            dry_run,
+            offset,
+            batch_size,
+        } => {
+            let options = ImportOptions {
+                batch_size,
+                offset,
+                dry_run,
+                on_progress: Some(Arc::new(|progress| {
+                    eprintln!("imported {} of {} events", progress.committed, progress.total)
+                })),
+            };
+            printer.rows(&[client.import_events(&file, &options).await?])?;
+        }
+        Command::Verify {
+            subject,
+            options,
+            contiguous_chain,
+        } => {
+            let verify = VerifyOptions {
+                contiguous_chain,
+                ..Default::default()
+            };
+            let report = client
+                .verify_events(&subject, options.into_options(), &verify)
+                .await?;
+            printer.rows(&[&report])?;
+            if !report.is_ok() {
+                return Err(report.to_string().into());
+            }
+        }
+        Command::Q { query } => printer.rows(&client.q(query).await?)?,
+        Command::Erase { subject } => {
+            client.erase_data(&subject).await?;
+            printer.text(&format!("erased {}", subject))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the events to commit, one JSON object per non-empty line
+fn read_events(path: &PathBuf) -> CliResult<Vec<CommitEvent>> {
+    let reader: Box<dyn BufRead> = if path.as_os_str() == "-" {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        Box::new(BufReader::new(std::fs::File::open(path)?))
+    };
+
+    let mut events = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line)
+            .map_err(|e| format!("{}:{}: {}", path.display(), index + 1, e))?;
+        events.push(event);
+    }
+    Ok(events)
+}
@@ -0,0 +1,230 @@
+//! Rendering of command results
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{self, Write};
+
+/// Output format of the command-line tool
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Pretty-printed JSON
+    Json,
+    /// One compact JSON document per line
+    Ndjson,
+    /// Aligned columns, one per top-level field
+    Table,
+}
+
+/// Writes results to stdout in the selected format
+pub struct Printer {
+    format: Format,
+    /// Columns of a table printed row by row, set by the first row
+    streamed_columns: Option<Vec<String>>,
+    /// Number of items of a JSON array printed item by item
+    streamed_items: usize,
+}
+
+impl Printer {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            streamed_columns: None,
+            streamed_items: 0,
+        }
+    }
+
+    /// Print a message, as a JSON string unless a table is printed
+    pub fn text(&mut self, text: &str) -> serde_json::Result<()> {
+        let mut out = io::stdout().lock();
+        match self.format {
+            Format::Json | Format::Ndjson => writeln!(out, "{}", serde_json::to_string(text)?),
+            Format::Table => writeln!(out, "{}", text),
+        }
+        .map_err(serde_json::Error::io)
+    }
+
+    /// Print a complete result set
+    pub fn rows<T: Serialize>(&mut self, rows: &[T]) -> serde_json::Result<()> {
+        let values = rows
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+
+        let mut out = io::stdout().lock();
+        match self.format {
+            Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(&values)?),
+            Format::Ndjson => values
+                .iter()
+                .try_for_each(|value| writeln!(out, "{}", value)),
+            Format::Table => write!(out, "{}", render_table(&values)),
+        }
+        .map_err(serde_json::Error::io)
+    }
+
+    /// Print one row of a result that arrives incrementally
+    ///
+    /// Tables cannot be aligned before all rows are known, so their columns
+    /// are separated by tabs instead.
+    pub fn row<T: Serialize>(&mut self, row: &T) -> serde_json::Result<()> {
+        let value = serde_json::to_value(row)?;
+
+        let mut out = io::stdout().lock();
+        match self.format {
+            Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(&value)?),
+            Format::Ndjson => writeln!(out, "{}", value),
+            Format::Table => {
+                let columns = self.streamed_columns.get_or_insert_with(|| {
+                    let columns = columns(std::slice::from_ref(&value));
+                    let _ = writeln!(out, "{}", columns.join("\t"));
+                    columns
+                });
+                let cells: Vec<String> = columns.iter().map(|column| cell(&value, column)).collect();
+                writeln!(out, "{}", cells.join("\t"))
+            }
+        }
+        .map_err(serde_json::Error::io)?;
+        out.flush().map_err(serde_json::Error::io)
+    }
+
+    /// Print one row of a finite result that arrives incrementally
+    ///
+    /// Printed like [`row`](Self::row), except that JSON rows form an array,
+    /// which [`end_items`](Self::end_items) closes.
+    pub fn item<T: Serialize>(&mut self, row: &T) -> serde_json::Result<()> {
+        if self.format != Format::Json {
+            return self.row(row);
+        }
+
+        // Literal newlines only separate tokens, strings escape theirs
+        let value = serde_json::to_string_pretty(row)?.replace('\n', "\n  ");
+        let separator = if self.streamed_items == 0 { "[" } else { "," };
+        self.streamed_items += 1;
+
+        let mut out = io::stdout().lock();
+        write!(out, "{}\n  {}", separator, value).map_err(serde_json::Error::io)?;
+        out.flush().map_err(serde_json::Error::io)
+    }
+
+    /// Finish a result printed with [`item`](Self::item)
+    pub fn end_items(&mut self) -> serde_json::Result<()> {
+        if self.format != Format::Json {
+            return Ok(());
+        }
+
+        let mut out = io::stdout().lock();
+        match self.streamed_items {
+            0 => writeln!(out, "[]"),
+            _ => writeln!(out, "\n]"),
+        }
+        .map_err(serde_json::Error::io)
+    }
+}
+
+/// Column used for rows that are not JSON objects
+const VALUE_COLUMN: &str = "value";
+
+/// Event attributes shown before all other fields, in this order
+const LEADING_COLUMNS: [&str; 5] = ["id", "time", "subject", "type", "source"];
+
+/// Top-level fields of the rows, event attributes first, the others sorted
+fn columns(rows: &[Value]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows {
+        match row {
+            Value::Object(fields) => {
+                for key in fields.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            _ if !columns.iter().any(|c| c == VALUE_COLUMN) => columns.push(VALUE_COLUMN.to_string()),
+            _ => {}
+        }
+    }
+    columns.sort_by_key(|column| {
+        LEADING_COLUMNS
+            .iter()
+            .position(|leading| leading == column)
+            .unwrap_or(LEADING_COLUMNS.len())
+    });
+    columns
+}
+
+/// Text of a field: strings without quotes, other values as compact JSON
+fn cell(row: &Value, column: &str) -> String {
+    let value = match row {
+        Value::Object(fields) => fields.get(column),
+        value if column == VALUE_COLUMN => Some(value),
+        _ => None,
+    };
+
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn render_table(rows: &[Value]) -> String {
+    let columns = columns(rows);
+    if columns.is_empty() {
+        return String::new();
+    }
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| columns.iter().map(|column| cell(row, column)).collect())
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut table = String::new();
+    for line in std::iter::once(&columns).chain(&cells) {
+        let padded: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .map(|(text, width)| format!("{:width$}", text, width = width))
+            .collect();
+        table.push_str(padded.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_table() {
+        let rows = vec![
+            json!({ "id": "1", "data": { "name": "Bruce" } }),
+            json!({ "id": "22", "type": "created" }),
+        ];
+
+        assert_eq!(
+            render_table(&rows),
+            concat!(
+                "id  type     data\n",
+                "1            {\"name\":\"Bruce\"}\n",
+                "22  created\n",
+            )
+        );
+        assert_eq!(render_table(&[json!(1), json!("a")]), "value\n1\na\n");
+        assert_eq!(render_table(&[]), "");
+    }
+}
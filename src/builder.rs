@@ -0,0 +1,347 @@
+//! Builder for configuring the GenesisDB client transport
+
+use crate::client::{Client, ClientConfig, Operation, Timeouts};
+use crate::error::{Error, Result};
+use crate::retry::RetryPolicy;
+use std::env;
+use std::fs;
+use std::time::Duration;
+
+/// Builder for a [`Client`] with custom HTTP settings
+///
+/// # Example
+///
+/// ```no_run
+/// # use genesisdb_io_client::{Client, ClientConfig, Operation};
+/// # use std::time::Duration;
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = Client::builder(ClientConfig {
+///     api_url: "https://genesisdb.internal:8080".to_string(),
+///     api_version: "v1".to_string(),
+///     auth_token: "token".to_string(),
+/// })
+/// .timeout(Duration::from_secs(30))
+/// .operation_timeout(Operation::Commit, Duration::from_secs(5))
+/// .connect_timeout(Duration::from_secs(2))
+/// .add_root_certificate(std::fs::read("ca.pem")?)
+/// .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ClientBuilder {
+    config: ClientConfig,
+    timeouts: Timeouts,
+    retry_policy: RetryPolicy,
+    connect_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    /// Settings paired with where they came from, for error messages
+    proxy: Option<(String, String)>,
+    root_certificates: Vec<(String, Vec<u8>)>,
+    identity: Option<(String, Vec<u8>, Vec<u8>)>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl ClientBuilder {
+    /// Create a new builder with the given configuration
+    pub fn new(config: ClientConfig) -> Self {
+        Self {
+            config,
+            timeouts: Timeouts::default(),
+            retry_policy: RetryPolicy::default(),
+            connect_timeout: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            proxy: None,
+            root_certificates: Vec::new(),
+            identity: None,
+            http_client: None,
+        }
+    }
+
+    /// Create a new builder from environment variables
+    ///
+    /// Reads the required variables of [`ClientConfig::from_env`] and the
+    /// following optional ones:
+    /// - `GENESISDB_TIMEOUT_MS` - default request timeout
+    /// - `GENESISDB_COMMIT_TIMEOUT_MS` - timeout for `commit_events`
+    /// - `GENESISDB_OBSERVE_TIMEOUT_MS` - timeout for `observe_events`
+    /// - `GENESISDB_CONNECT_TIMEOUT_MS` - connect timeout
+    /// - `GENESISDB_POOL_MAX_IDLE_PER_HOST` - maximum idle connections per host
+    /// - `GENESISDB_PROXY` - proxy URL for all requests
+    /// - `GENESISDB_CA_CERT` - path to a PEM bundle of trusted root certificates
+    /// - `GENESISDB_CLIENT_CERT` and `GENESISDB_CLIENT_KEY` - paths to a PEM
+    ///   client certificate chain and PKCS#8 private key for mutual TLS
+    pub fn from_env() -> Result<Self> {
+        Self::new(ClientConfig::from_env()?).transport_from_env()
+    }
+
+    /// Apply the optional transport settings of [`from_env`](Self::from_env)
+    ///
+    /// Useful when the configuration comes from elsewhere, e.g. command-line
+    /// flags, but timeouts, proxy and certificates are still set in the
+    /// environment.
+    pub fn transport_from_env(self) -> Result<Self> {
+        let mut builder = self;
+
+        if let Some(timeout) = env_millis("GENESISDB_TIMEOUT_MS")? {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = env_millis("GENESISDB_COMMIT_TIMEOUT_MS")? {
+            builder = builder.operation_timeout(Operation::Commit, timeout);
+        }
+        if let Some(timeout) = env_millis("GENESISDB_OBSERVE_TIMEOUT_MS")? {
+            builder = builder.operation_timeout(Operation::Observe, timeout);
+        }
+        if let Some(timeout) = env_millis("GENESISDB_CONNECT_TIMEOUT_MS")? {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(max) = env_parse::<usize>("GENESISDB_POOL_MAX_IDLE_PER_HOST")? {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Ok(proxy) = env::var("GENESISDB_PROXY") {
+            builder.proxy = Some(("GENESISDB_PROXY".to_string(), proxy));
+        }
+        if let Ok(path) = env::var("GENESISDB_CA_CERT") {
+            let pem = read_file(&path)?;
+            builder.root_certificates.push((format!("GENESISDB_CA_CERT ({})", path), pem));
+        }
+        match (env::var("GENESISDB_CLIENT_CERT"), env::var("GENESISDB_CLIENT_KEY")) {
+            (Ok(cert), Ok(key)) => {
+                let source = format!("GENESISDB_CLIENT_CERT ({}) and GENESISDB_CLIENT_KEY ({})", cert, key);
+                builder.identity = Some((source, read_file(&cert)?, read_file(&key)?));
+            }
+            (Ok(_), Err(_)) => {
+                return Err(Error::EnvError("GENESISDB_CLIENT_KEY not set".to_string()))
+            }
+            (Err(_), Ok(_)) => {
+                return Err(Error::EnvError("GENESISDB_CLIENT_CERT not set".to_string()))
+            }
+            (Err(_), Err(_)) => {}
+        }
+
+        Ok(builder)
+    }
+
+    /// Set the default timeout for every request
+    ///
+    /// A request timeout also covers reading the response body, so it does
+    /// not apply to the streaming operations [`Operation::Stream`],
+    /// [`Operation::Query`] and [`Operation::Observe`]. They only time out
+    /// when configured with [`operation_timeout`](Self::operation_timeout).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.default = Some(timeout);
+        self
+    }
+
+    /// Set the timeout for a single operation, overriding the default
+    pub fn operation_timeout(mut self, operation: Operation, timeout: Duration) -> Self {
+        self.timeouts.per_operation.insert(operation, timeout);
+        self
+    }
+
+    /// Retry transient failures according to the given policy
+    ///
+    /// By default [`RetryPolicy::default`] is used, 3 attempts with
+    /// exponential backoff. Use [`RetryPolicy::none`] to disable retries.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Set the timeout for establishing a connection
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum number of idle connections kept per host
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Set how long idle connections are kept in the pool
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Route all requests through the given proxy URL
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(("proxy".to_string(), url.into()));
+        self
+    }
+
+    /// Trust the root certificates in the given PEM bundle
+    pub fn add_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        let source = format!("root certificate {}", self.root_certificates.len() + 1);
+        self.root_certificates.push((source, pem.into()));
+        self
+    }
+
+    /// Authenticate with a client certificate for mutual TLS
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_pem` - PEM encoded certificate chain
+    /// * `key_pem` - PEM encoded PKCS#8 private key
+    pub fn identity(mut self, cert_pem: impl Into<Vec<u8>>, key_pem: impl Into<Vec<u8>>) -> Self {
+        self.identity = Some(("client identity".to_string(), cert_pem.into(), key_pem.into()));
+        self
+    }
+
+    /// Use a pre-built HTTP client
+    ///
+    /// Connect timeout, pooling, proxy and TLS settings of this builder are
+    /// ignored; configure them on the given client instead. Request timeouts
+    /// are still applied per operation.
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Build the client
+    ///
+    /// An invalid proxy URL, certificate or key is reported as
+    /// [`Error::InvalidConfig`], naming the setting or environment variable
+    /// it came from.
+    pub fn build(self) -> Result<Client> {
+        self.config.validate()?;
+
+        let http_client = match self.http_client {
+            Some(http_client) => http_client,
+            None => {
+                let mut builder = reqwest::Client::builder();
+
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(max) = self.pool_max_idle_per_host {
+                    builder = builder.pool_max_idle_per_host(max);
+                }
+                if let Some(timeout) = self.pool_idle_timeout {
+                    builder = builder.pool_idle_timeout(timeout);
+                }
+                if let Some((source, url)) = &self.proxy {
+                    let proxy = reqwest::Proxy::all(url)
+                        .map_err(|e| Error::InvalidConfig(format!("invalid {} URL {:?}: {}", source, url, e)))?;
+                    builder = builder.proxy(proxy);
+                }
+                for (source, pem) in &self.root_certificates {
+                    let certificates = reqwest::Certificate::from_pem_bundle(pem)
+                        .map_err(|e| Error::InvalidConfig(format!("invalid {}: {}", source, e)))?;
+                    if certificates.is_empty() {
+                        return Err(Error::InvalidConfig(format!("invalid {}: no PEM certificate found", source)));
+                    }
+                    for certificate in certificates {
+                        builder = builder.add_root_certificate(certificate);
+                    }
+                }
+                if let Some((source, cert, key)) = &self.identity {
+                    let identity = reqwest::Identity::from_pkcs8_pem(cert, key)
+                        .map_err(|e| Error::InvalidConfig(format!("invalid {}: {}", source, e)))?;
+                    builder = builder.identity(identity);
+                }
+
+                builder.build()?
+            }
+        };
+
+        Ok(Client::from_parts(
+            self.config,
+            http_client,
+            self.timeouts,
+            self.retry_policy,
+        ))
+    }
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str) -> Result<Option<T>> {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::EnvError(format!("{} has an invalid value: {}", name, value))),
+        _ => Ok(None),
+    }
+}
+
+fn env_millis(name: &str) -> Result<Option<Duration>> {
+    Ok(env_parse::<u64>(name)?.map(Duration::from_millis))
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| Error::InvalidConfig(format!("cannot read {}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ClientConfig {
+        ClientConfig {
+            api_url: "http://localhost:8080".to_string(),
+            api_version: "v1".to_string(),
+            auth_token: "token".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_builder_validates_config() {
+        let mut invalid = config();
+        invalid.auth_token = String::new();
+        assert!(matches!(
+            ClientBuilder::new(invalid).build(),
+            Err(Error::MissingConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_builder_with_transport_settings() {
+        let client = ClientBuilder::new(config())
+            .timeout(Duration::from_secs(10))
+            .operation_timeout(Operation::Commit, Duration::from_secs(2))
+            .connect_timeout(Duration::from_secs(1))
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(Duration::from_secs(60))
+            .proxy("http://proxy.local:3128")
+            .build();
+        assert!(client.is_ok());
+    }
+
+    #[test]
+    fn test_builder_with_http_client() {
+        let client = ClientBuilder::new(config())
+            .http_client(reqwest::Client::new())
+            .build();
+        assert!(client.is_ok());
+    }
+
+    fn build_error(builder: ClientBuilder) -> String {
+        match builder.build() {
+            Err(Error::InvalidConfig(message)) => message,
+            Err(other) => panic!("expected InvalidConfig, got {:?}", other),
+            Ok(_) => panic!("expected InvalidConfig, got a client"),
+        }
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_proxy() {
+        let message = build_error(ClientBuilder::new(config()).proxy("not a url"));
+        assert!(message.starts_with("invalid proxy URL \"not a url\""), "{}", message);
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_root_certificate() {
+        let message = build_error(ClientBuilder::new(config()).add_root_certificate("not a certificate"));
+        assert_eq!(message, "invalid root certificate 1: no PEM certificate found");
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_identity() {
+        let message = build_error(ClientBuilder::new(config()).identity("not a certificate", "not a key"));
+        assert!(message.starts_with("invalid client identity: "), "{}", message);
+    }
+}
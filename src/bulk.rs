@@ -0,0 +1,399 @@
+//! Committing large batches of events in chunks
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::idempotency::IdempotencyKey;
+use crate::types::{CommitEvent, CommitResult, CommittedEvent};
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Size of the request body around the events, `{"events":[]}`
+const BODY_OVERHEAD: usize = 13;
+
+/// Callback invoked after every committed chunk
+pub type ProgressCallback = Arc<dyn Fn(&BulkProgress) + Send + Sync>;
+
+/// Progress of a bulk commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkProgress {
+    /// Positions of the events of the chunk that was just committed
+    pub chunk: Range<usize>,
+    /// Number of events committed so far
+    pub committed: usize,
+    /// Number of events of the whole batch
+    pub total: usize,
+}
+
+/// Options for committing a batch of events in chunks
+///
+/// A chunk is closed as soon as adding the next event would exceed either
+/// limit. An event that exceeds `max_chunk_bytes` on its own is sent in a
+/// chunk of its own.
+///
+/// # Example
+///
+/// ```
+/// # use genesisdb_io_client::BulkOptions;
+/// # use std::sync::Arc;
+/// let options = BulkOptions {
+///     max_events_per_chunk: 500,
+///     concurrency: 4,
+///     on_progress: Some(Arc::new(|progress| {
+///         eprintln!("committed {} of {} events", progress.committed, progress.total)
+///     })),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct BulkOptions {
+    /// Maximum number of events per chunk
+    pub max_events_per_chunk: usize,
+    /// Maximum size of the serialized events of a chunk, in bytes
+    pub max_chunk_bytes: usize,
+    /// Maximum number of chunks committed at the same time
+    ///
+    /// With more than one, chunks may be stored out of order, so events of
+    /// a later chunk can precede those of an earlier one in the store.
+    pub concurrency: usize,
+    /// Called after every committed chunk
+    pub on_progress: Option<ProgressCallback>,
+}
+
+impl Default for BulkOptions {
+    fn default() -> Self {
+        Self {
+            max_events_per_chunk: 1000,
+            max_chunk_bytes: 4 * 1024 * 1024,
+            concurrency: 1,
+            on_progress: None,
+        }
+    }
+}
+
+impl fmt::Debug for BulkOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BulkOptions")
+            .field("max_events_per_chunk", &self.max_events_per_chunk)
+            .field("max_chunk_bytes", &self.max_chunk_bytes)
+            .field("concurrency", &self.concurrency)
+            .field("on_progress", &self.on_progress.is_some())
+            .finish()
+    }
+}
+
+impl BulkOptions {
+    fn validate(&self) -> Result<()> {
+        if self.max_events_per_chunk == 0 {
+            return Err(Error::InvalidConfig(
+                "max_events_per_chunk must be greater than 0".to_string(),
+            ));
+        }
+        if self.concurrency == 0 {
+            return Err(Error::InvalidConfig(
+                "concurrency must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Events of a batch that are known to be committed
+///
+/// Returned in [`Error::BulkCommitFailed`] and passed to
+/// [`Client::resume_commit_events_bulk`] to commit the remaining events.
+/// Serializable, so it can be persisted between runs.
+///
+/// Holds the idempotency key of the batch. Each chunk is committed under
+/// `<batch key>/<chunk start>`, so a chunk that was stored although it was
+/// reported as failed is not committed twice on resume.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkCheckpoint {
+    #[serde(default)]
+    key: IdempotencyKey,
+    chunks: Vec<CommittedChunk>,
+}
+
+impl Default for BulkCheckpoint {
+    /// Empty checkpoint with a random batch key
+    fn default() -> Self {
+        Self::new(IdempotencyKey::new())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CommittedChunk {
+    range: Range<usize>,
+    events: Vec<CommittedEvent>,
+}
+
+impl BulkCheckpoint {
+    /// Empty checkpoint for a batch committed under the given key
+    ///
+    /// Use a key persisted with the batch to make even the first attempt
+    /// resumable after a crash.
+    pub fn new(key: IdempotencyKey) -> Self {
+        Self {
+            key,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Idempotency key of the batch
+    pub fn key(&self) -> &IdempotencyKey {
+        &self.key
+    }
+
+    /// Number of committed events
+    pub fn committed_events(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.range.len()).sum()
+    }
+
+    /// Whether the event at the given position of the batch was committed
+    pub fn is_committed(&self, index: usize) -> bool {
+        self.chunks.iter().any(|chunk| chunk.range.contains(&index))
+    }
+
+    /// Positions of the committed events, in order
+    pub fn committed_ranges(&self) -> Vec<Range<usize>> {
+        self.chunks.iter().map(|chunk| chunk.range.clone()).collect()
+    }
+
+    /// Ids and times of the committed events, in batch order
+    pub fn result(&self) -> CommitResult {
+        CommitResult {
+            events: self
+                .chunks
+                .iter()
+                .flat_map(|chunk| chunk.events.iter().cloned())
+                .collect(),
+        }
+    }
+
+    fn insert(&mut self, chunk: CommittedChunk) {
+        let position = self
+            .chunks
+            .partition_point(|committed| committed.range.start < chunk.range.start);
+        self.chunks.insert(position, chunk);
+    }
+}
+
+/// A chunk of events still to be committed
+struct Chunk {
+    range: Range<usize>,
+    events: Vec<CommitEvent>,
+}
+
+/// Split the events that are not committed yet into chunks of contiguous positions
+fn split_into_chunks(
+    events: Vec<CommitEvent>,
+    checkpoint: &BulkCheckpoint,
+    options: &BulkOptions,
+) -> Result<Vec<Chunk>> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut bytes = 0;
+
+    for (index, event) in events.into_iter().enumerate() {
+        if checkpoint.is_committed(index) {
+            continue;
+        }
+
+        // One separating comma per event, which over-counts the first by one
+        let size = serde_json::to_vec(&event)?.len() + 1;
+
+        match chunks.last_mut() {
+            Some(chunk)
+                if chunk.range.end == index
+                    && chunk.events.len() < options.max_events_per_chunk
+                    && bytes + size <= options.max_chunk_bytes =>
+            {
+                chunk.range.end += 1;
+                chunk.events.push(event);
+                bytes += size;
+            }
+            _ => {
+                chunks.push(Chunk {
+                    range: index..index + 1,
+                    events: vec![event],
+                });
+                bytes = BODY_OVERHEAD + size;
+            }
+        }
+    }
+
+    Ok(chunks)
+}
+
+impl Client {
+    /// Commit a large batch of events in chunks
+    ///
+    /// The events are split into chunks by [`BulkOptions::max_events_per_chunk`]
+    /// and [`BulkOptions::max_chunk_bytes`] and committed in order, or with up
+    /// to [`BulkOptions::concurrency`] chunks in flight.
+    ///
+    /// **Each chunk is committed atomically, the batch as a whole is not.**
+    /// If a chunk fails, no further chunks are started and
+    /// [`Error::BulkCommitFailed`] is returned with a [`BulkCheckpoint`] of
+    /// the chunks that were committed. Pass it to
+    /// [`resume_commit_events_bulk`](Client::resume_commit_events_bulk) to
+    /// commit the rest. With concurrency, chunks after the failed one may
+    /// already be committed; the checkpoint accounts for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - Events to commit
+    /// * `options` - Chunk limits, concurrency and progress callback
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::{BulkOptions, Client, CommitEvent, Error};
+    /// # async fn example(client: Client, events: Vec<CommitEvent>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let options = BulkOptions::default();
+    ///
+    /// match client.commit_events_bulk(events.clone(), &options).await {
+    ///     Ok(result) => println!("committed {} events", result.events.len()),
+    ///     Err(Error::BulkCommitFailed { checkpoint, .. }) => {
+    ///         client.resume_commit_events_bulk(events, &checkpoint, &options).await?;
+    ///     }
+    ///     Err(e) => return Err(e.into()),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn commit_events_bulk(
+        &self,
+        events: Vec<CommitEvent>,
+        options: &BulkOptions,
+    ) -> Result<CommitResult> {
+        self.resume_commit_events_bulk(events, &BulkCheckpoint::default(), options)
+            .await
+    }
+
+    /// Commit the events of a batch that are not in the checkpoint
+    ///
+    /// `events` must be the same batch, in the same order, that the
+    /// checkpoint was created for, and `options` must have the same chunk
+    /// limits, so the chunks are split as before. Returns the result for the
+    /// whole batch, including the events committed before.
+    ///
+    /// Chunks are committed with
+    /// [`commit_events_idempotent`](Client::commit_events_idempotent) under a
+    /// key derived from [`BulkCheckpoint::key`] and the chunk start, so a
+    /// chunk that was stored although its commit failed is not written again.
+    pub async fn resume_commit_events_bulk(
+        &self,
+        events: Vec<CommitEvent>,
+        checkpoint: &BulkCheckpoint,
+        options: &BulkOptions,
+    ) -> Result<CommitResult> {
+        options.validate()?;
+
+        let total = events.len();
+        let mut checkpoint = checkpoint.clone();
+        let mut pending = split_into_chunks(events, &checkpoint, options)?.into_iter();
+        let mut in_flight = FuturesUnordered::new();
+        let mut failure = None;
+
+        loop {
+            while failure.is_none() && in_flight.len() < options.concurrency {
+                let Some(chunk) = pending.next() else {
+                    break;
+                };
+                let key = IdempotencyKey::from(format!("{}/{}", checkpoint.key, chunk.range.start));
+                in_flight.push(async move {
+                    let result = self.commit_events_idempotent(chunk.events, None, &key).await;
+                    (chunk.range, result)
+                });
+            }
+
+            let Some((range, result)) = in_flight.next().await else {
+                break;
+            };
+
+            match result {
+                Ok(result) => {
+                    checkpoint.insert(CommittedChunk {
+                        range: range.clone(),
+                        events: result.events,
+                    });
+                    if let Some(on_progress) = &options.on_progress {
+                        on_progress(&BulkProgress {
+                            chunk: range,
+                            committed: checkpoint.committed_events(),
+                            total,
+                        });
+                    }
+                }
+                Err(error) => {
+                    failure.get_or_insert(error);
+                }
+            }
+        }
+
+        match failure {
+            Some(error) => Err(Error::BulkCommitFailed {
+                checkpoint,
+                source: Box::new(error),
+            }),
+            None => Ok(checkpoint.result()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(size: usize) -> CommitEvent {
+        CommitEvent {
+            source: "test".to_string(),
+            subject: "/test".to_string(),
+            event_type: "test".to_string(),
+            data: json!("x".repeat(size)),
+            ..Default::default()
+        }
+    }
+
+    fn ranges(chunks: &[Chunk]) -> Vec<Range<usize>> {
+        chunks.iter().map(|chunk| chunk.range.clone()).collect()
+    }
+
+    #[test]
+    fn test_split_by_count() {
+        let options = BulkOptions {
+            max_events_per_chunk: 2,
+            ..Default::default()
+        };
+        let chunks = split_into_chunks(vec![event(1); 5], &BulkCheckpoint::default(), &options).unwrap();
+        assert_eq!(ranges(&chunks), vec![0..2, 2..4, 4..5]);
+    }
+
+    #[test]
+    fn test_split_by_bytes() {
+        let size = serde_json::to_vec(&event(100)).unwrap().len() + 1;
+        let options = BulkOptions {
+            max_chunk_bytes: BODY_OVERHEAD + 2 * size,
+            ..Default::default()
+        };
+        let events = vec![event(100), event(100), event(100), event(1000), event(100)];
+        let chunks = split_into_chunks(events, &BulkCheckpoint::default(), &options).unwrap();
+        // The oversized event is sent on its own
+        assert_eq!(ranges(&chunks), vec![0..2, 2..3, 3..4, 4..5]);
+    }
+
+    #[test]
+    fn test_split_skips_committed_events() {
+        let mut checkpoint = BulkCheckpoint::default();
+        checkpoint.insert(CommittedChunk {
+            range: 2..4,
+            events: vec![CommittedEvent::default(); 2],
+        });
+        let chunks = split_into_chunks(vec![event(1); 6], &checkpoint, &BulkOptions::default()).unwrap();
+        assert_eq!(ranges(&chunks), vec![0..2, 4..6]);
+        assert_eq!(checkpoint.committed_events(), 2);
+    }
+}
@@ -0,0 +1,171 @@
+//! Persisted positions of projections
+
+use crate::error::Result;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+
+/// Storage for the id of the last event a projection processed
+///
+/// Implement it to keep checkpoints next to the read model, so both survive
+/// restarts together.
+pub trait CheckpointStore: Send + Sync {
+    /// Id of the last event processed by the named projection, if any
+    fn load(&self, projection: &str) -> impl Future<Output = Result<Option<String>>> + Send;
+
+    /// Remember the id of the last event processed by the named projection
+    fn save(&self, projection: &str, event_id: &str) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Checkpoints kept in memory, lost when the process exits
+///
+/// Clones share the same checkpoints.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryCheckpointStore {
+    checkpoints: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl InMemoryCheckpointStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CheckpointStore for InMemoryCheckpointStore {
+    async fn load(&self, projection: &str) -> Result<Option<String>> {
+        Ok(self.checkpoints.lock().unwrap().get(projection).cloned())
+    }
+
+    async fn save(&self, projection: &str, event_id: &str) -> Result<()> {
+        self.checkpoints
+            .lock()
+            .unwrap()
+            .insert(projection.to_string(), event_id.to_string());
+        Ok(())
+    }
+}
+
+/// Checkpoints kept in a JSON file, mapping projection names to event ids
+///
+/// Every save writes and syncs a temporary file, then renames it over the
+/// checkpoint file and syncs the directory, so a crash never leaves a
+/// partially written checkpoint behind. The file is read once and then kept
+/// in memory; several projections can share one file through clones of the
+/// same store.
+#[derive(Debug, Clone)]
+pub struct FileCheckpointStore {
+    path: PathBuf,
+    checkpoints: Arc<tokio::sync::Mutex<Option<HashMap<String, String>>>>,
+}
+
+impl FileCheckpointStore {
+    /// Create a store backed by the given file, which is created on the first save
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            checkpoints: Arc::default(),
+        }
+    }
+
+    /// Path of the checkpoint file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn read(&self) -> Result<HashMap<String, String>> {
+        match tokio::fs::read(&self.path).await {
+            Ok(content) => Ok(serde_json::from_slice(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Durably replace the checkpoint file
+    async fn write(&self, checkpoints: &HashMap<String, String>) -> Result<()> {
+        write_durably(&self.path, &serde_json::to_vec_pretty(checkpoints)?).await
+    }
+}
+
+/// Replace a file so that a crash leaves either the old or the new content
+///
+/// Writes and syncs a temporary file next to it, renames it over the file
+/// and syncs the directory.
+pub(crate) async fn write_durably(path: &Path, content: &[u8]) -> Result<()> {
+    let mut temp = path.to_path_buf().into_os_string();
+    temp.push(".tmp");
+
+    let mut file = tokio::fs::File::create(&temp).await?;
+    file.write_all(content).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    tokio::fs::rename(&temp, path).await?;
+    sync_parent_dir(path).await
+}
+
+/// Persist a rename by syncing the directory holding the file
+#[cfg(unix)]
+async fn sync_parent_dir(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    tokio::fs::File::open(dir).await?.sync_all().await?;
+    Ok(())
+}
+
+/// Directories cannot be opened for syncing on this platform
+#[cfg(not(unix))]
+async fn sync_parent_dir(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+impl CheckpointStore for FileCheckpointStore {
+    async fn load(&self, projection: &str) -> Result<Option<String>> {
+        let mut cached = self.checkpoints.lock().await;
+        let checkpoints = match cached.as_mut() {
+            Some(checkpoints) => checkpoints,
+            None => cached.insert(self.read().await?),
+        };
+        Ok(checkpoints.get(projection).cloned())
+    }
+
+    async fn save(&self, projection: &str, event_id: &str) -> Result<()> {
+        let mut cached = self.checkpoints.lock().await;
+        let mut checkpoints = match cached.take() {
+            Some(checkpoints) => checkpoints,
+            None => self.read().await?,
+        };
+        checkpoints.insert(projection.to_string(), event_id.to_string());
+
+        // On failure the cache stays empty, so the next access reads the file again
+        self.write(&checkpoints).await?;
+        *cached = Some(checkpoints);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_store_round_trip() {
+        let path = std::env::temp_dir().join(format!("genesisdb-checkpoints-{}.json", uuid::Uuid::new_v4()));
+        let store = FileCheckpointStore::new(&path);
+
+        assert_eq!(store.load("orders").await.unwrap(), None);
+        store.save("orders", "a").await.unwrap();
+        store.save("users", "b").await.unwrap();
+        store.save("orders", "c").await.unwrap();
+
+        let reopened = FileCheckpointStore::new(&path);
+        assert_eq!(reopened.load("orders").await.unwrap().as_deref(), Some("c"));
+        assert_eq!(reopened.load("users").await.unwrap().as_deref(), Some("b"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
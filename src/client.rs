@@ -1,12 +1,27 @@
 //! GenesisDB client implementation
 
 use crate::error::{Error, Result};
+use crate::ndjson;
+use crate::retry::{RetryEvent, RetryPolicy};
+use crate::telemetry::{self, OperationSpan};
 use crate::types::*;
-use futures::stream::{Stream, StreamExt};
+use crate::validation::extension_name_violation;
+use futures::stream::{Stream, StreamExt, TryStreamExt};
 use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
+use reqwest::{Method, RequestBuilder};
+use serde::de::DeserializeOwned;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::env;
 use std::pin::Pin;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// A stream of CloudEvents decoded incrementally from an NDJSON response
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<CloudEvent>> + Send>>;
+
+/// A stream of query result rows
+pub type RowStream<T = Value> = Pin<Box<dyn Stream<Item = Result<T>> + Send>>;
 
 /// Configuration for the GenesisDB client
 #[derive(Debug, Clone)]
@@ -26,6 +41,9 @@ impl ClientConfig {
     /// - `GENESISDB_API_URL`
     /// - `GENESISDB_API_VERSION`
     /// - `GENESISDB_AUTH_TOKEN`
+    ///
+    /// Optional transport settings (timeouts, proxy, certificates) are read
+    /// by [`ClientBuilder::from_env`](crate::ClientBuilder::from_env).
     pub fn from_env() -> Result<Self> {
         let api_url = env::var("GENESISDB_API_URL")
             .map_err(|_| Error::EnvError("GENESISDB_API_URL not set".to_string()))?;
@@ -40,6 +58,78 @@ impl ClientConfig {
             auth_token,
         })
     }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.api_url.is_empty() {
+            return Err(Error::MissingConfig("api_url".to_string()));
+        }
+        if self.api_version.is_empty() {
+            return Err(Error::MissingConfig("api_version".to_string()));
+        }
+        if self.auth_token.is_empty() {
+            return Err(Error::MissingConfig("auth_token".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Operations performed by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// `ping`
+    Ping,
+    /// `audit`
+    Audit,
+    /// `stream_events`
+    Stream,
+    /// `commit_events`
+    Commit,
+    /// `erase_data`
+    Erase,
+    /// `q` and `query_events`
+    Query,
+    /// `observe_events`
+    Observe,
+}
+
+impl Operation {
+    /// Whether the response is streamed and may take arbitrarily long to read
+    fn is_streaming(&self) -> bool {
+        matches!(self, Operation::Stream | Operation::Query | Operation::Observe)
+    }
+
+    /// Lowercase name of the operation, e.g. for logs and metrics
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Ping => "ping",
+            Operation::Audit => "audit",
+            Operation::Stream => "stream",
+            Operation::Commit => "commit",
+            Operation::Erase => "erase",
+            Operation::Query => "query",
+            Operation::Observe => "observe",
+        }
+    }
+}
+
+/// Request timeouts, configurable per operation
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Timeouts {
+    /// Timeout for every non-streaming operation without its own timeout
+    pub default: Option<Duration>,
+    pub per_operation: HashMap<Operation, Duration>,
+}
+
+impl Timeouts {
+    fn get(&self, operation: Operation) -> Option<Duration> {
+        match self.per_operation.get(&operation) {
+            Some(timeout) => Some(*timeout),
+            // A request timeout also covers reading the body, so streamed
+            // responses only time out when set explicitly
+            None if operation.is_streaming() => None,
+            None => self.default,
+        }
+    }
 }
 
 /// GenesisDB client
@@ -47,33 +137,44 @@ impl ClientConfig {
 pub struct Client {
     config: ClientConfig,
     http_client: reqwest::Client,
+    timeouts: Timeouts,
+    retry_policy: RetryPolicy,
 }
 
 impl Client {
     /// Create a new GenesisDB client with the given configuration
+    ///
+    /// Use [`ClientBuilder`](crate::ClientBuilder) to configure timeouts,
+    /// proxies or TLS settings.
     pub fn new(config: ClientConfig) -> Result<Self> {
-        if config.api_url.is_empty() {
-            return Err(Error::MissingConfig("api_url".to_string()));
-        }
-        if config.api_version.is_empty() {
-            return Err(Error::MissingConfig("api_version".to_string()));
-        }
-        if config.auth_token.is_empty() {
-            return Err(Error::MissingConfig("auth_token".to_string()));
-        }
-
-        let http_client = reqwest::Client::new();
-
-        Ok(Self {
-            config,
-            http_client,
-        })
+        crate::ClientBuilder::new(config).build()
     }
 
     /// Create a new GenesisDB client from environment variables
+    ///
+    /// See [`ClientBuilder::from_env`](crate::ClientBuilder::from_env) for
+    /// the variables that are read.
     pub fn from_env() -> Result<Self> {
-        let config = ClientConfig::from_env()?;
-        Self::new(config)
+        crate::ClientBuilder::from_env()?.build()
+    }
+
+    /// Create a builder for a client with the given configuration
+    pub fn builder(config: ClientConfig) -> crate::ClientBuilder {
+        crate::ClientBuilder::new(config)
+    }
+
+    pub(crate) fn from_parts(
+        config: ClientConfig,
+        http_client: reqwest::Client,
+        timeouts: Timeouts,
+        retry_policy: RetryPolicy,
+    ) -> Self {
+        Self {
+            config,
+            http_client,
+            timeouts,
+            retry_policy,
+        }
     }
 
     fn build_url(&self, path: &str) -> String {
@@ -97,58 +198,111 @@ impl Client {
         headers
     }
 
+    fn request(&self, operation: Operation, method: Method, path: &str) -> RequestBuilder {
+        let builder = self.http_client.request(method, self.build_url(path));
+        match self.timeouts.get(operation) {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        }
+    }
+
+    /// Send a request, retrying transient failures if `retryable` is set
+    ///
+    /// `prepare` is called for every attempt to add headers and body to a
+    /// fresh request.
+    async fn send<F>(
+        &self,
+        operation: Operation,
+        method: Method,
+        path: &str,
+        retryable: bool,
+        prepare: F,
+    ) -> Result<reqwest::Response>
+    where
+        F: Fn(RequestBuilder) -> RequestBuilder,
+    {
+        let mut attempt = 1;
+
+        loop {
+            let request = prepare(self.request(operation, method.clone(), path));
+            let started = Instant::now();
+            let result = match request.send().await {
+                Ok(response) => {
+                    telemetry::response(operation, Some(response.status().as_u16()), started.elapsed());
+                    check_response(response).await
+                }
+                Err(e) => {
+                    telemetry::response(operation, None, started.elapsed());
+                    Err(Error::RequestError(e))
+                }
+            };
+
+            match result {
+                Err(error) if retryable && self.retry_policy.should_retry(attempt, &error) => {
+                    let delay = self.retry_policy.delay(attempt, &error);
+                    telemetry::retry(attempt, delay, &error);
+                    self.retry_policy.notify(&RetryEvent {
+                        operation,
+                        attempt,
+                        delay,
+                        error: &error,
+                    });
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Ping the GenesisDB server
     ///
     /// Returns "pong" if the server is healthy
     pub async fn ping(&self) -> Result<String> {
-        let url = self.build_url("status/ping");
-
         let mut headers = self.default_headers();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
 
-        let response = self
-            .http_client
-            .get(&url)
-            .headers(headers)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(Error::ApiError {
-                status: response.status().as_u16(),
-                status_text: response.status().canonical_reason().unwrap_or("Unknown").to_string(),
-            });
-        }
-
-        Ok(response.text().await?)
+        OperationSpan::new(Operation::Ping)
+            .run(async {
+                let response = self
+                    .send(Operation::Ping, Method::GET, "status/ping", true, |request| {
+                        request.headers(headers.clone())
+                    })
+                    .await?;
+
+                let body = response.text().await?;
+                telemetry::bytes_received(body.len());
+                Ok(body)
+            })
+            .await
     }
 
     /// Get audit information from the GenesisDB server
     pub async fn audit(&self) -> Result<String> {
-        let url = self.build_url("status/audit");
-
         let mut headers = self.default_headers();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
 
-        let response = self
-            .http_client
-            .get(&url)
-            .headers(headers)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(Error::ApiError {
-                status: response.status().as_u16(),
-                status_text: response.status().canonical_reason().unwrap_or("Unknown").to_string(),
-            });
-        }
-
-        Ok(response.text().await?)
+        OperationSpan::new(Operation::Audit)
+            .run(async {
+                let response = self
+                    .send(Operation::Audit, Method::GET, "status/audit", true, |request| {
+                        request.headers(headers.clone())
+                    })
+                    .await?;
+
+                let body = response.text().await?;
+                telemetry::bytes_received(body.len());
+                Ok(body)
+            })
+            .await
     }
 
     /// Stream events for a given subject
     ///
+    /// Collects the whole response into memory. Use
+    /// [`stream_events_iter`](Self::stream_events_iter) to process large
+    /// subjects event by event.
+    ///
     /// # Arguments
     ///
     /// * `subject` - The subject to stream events for
@@ -176,8 +330,54 @@ impl Client {
         subject: &str,
         options: Option<StreamOptions>,
     ) -> Result<Vec<CloudEvent>> {
-        let url = self.build_url("stream");
+        self.stream_events_iter(subject, options)
+            .await?
+            .try_collect()
+            .await
+    }
+
+    /// Stream events for a given subject without buffering the response
+    ///
+    /// Events are parsed as soon as their line has been received, so memory
+    /// usage stays constant regardless of how many events the subject holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `subject` - The subject to stream events for
+    /// * `options` - Optional streaming options
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::{Client, ClientConfig};
+    /// # use futures::StreamExt;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::new(ClientConfig {
+    /// #     api_url: "http://localhost:8080".to_string(),
+    /// #     api_version: "v1".to_string(),
+    /// #     auth_token: "token".to_string(),
+    /// # })?;
+    /// let mut stream = client.stream_events_iter("/", None).await?;
+    /// while let Some(event) = stream.next().await {
+    ///     println!("Event: {:?}", event?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn stream_events_iter(
+        &self,
+        subject: &str,
+        options: Option<StreamOptions>,
+    ) -> Result<EventStream> {
+        self.stream_events_as(subject, options).await
+    }
 
+    /// Stream events for a given subject, deserialized into `T`
+    pub(crate) async fn stream_events_as<T: DeserializeOwned + Send + 'static>(
+        &self,
+        subject: &str,
+        options: Option<StreamOptions>,
+    ) -> Result<RowStream<T>> {
         let mut headers = self.default_headers();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
         headers.insert(ACCEPT, HeaderValue::from_static("application/x-ndjson"));
@@ -187,38 +387,37 @@ impl Client {
             options,
         };
 
-        let response = self
-            .http_client
-            .post(&url)
-            .headers(headers)
-            .json(&request_body)
-            .send()
+        let span = OperationSpan::new(Operation::Stream).subject(subject);
+        let response = span
+            .run(self.send(Operation::Stream, Method::POST, "stream", true, |request| {
+                request.headers(headers.clone()).json(&request_body)
+            }))
             .await?;
 
-        if !response.status().is_success() {
-            return Err(Error::ApiError {
-                status: response.status().as_u16(),
-                status_text: response.status().canonical_reason().unwrap_or("Unknown").to_string(),
-            });
-        }
-
-        let text = response.text().await?;
-
-        if text.trim().is_empty() {
-            return Ok(Vec::new());
-        }
+        let event_stream = ndjson::numbered_lines(response.bytes_stream()).map(|line| {
+            let (line, text) = line?;
+            let event = serde_json::from_str(&text)
+                .inspect_err(|e| telemetry::parse_failure(Operation::Stream, line, e))?;
+            telemetry::event_received(Operation::Stream);
+            Ok(event)
+        });
 
-        let events: Vec<CloudEvent> = text
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| serde_json::from_str(line))
-            .collect::<std::result::Result<Vec<_>, _>>()?;
-
-        Ok(events)
+        Ok(Box::pin(span.stream(event_stream)))
     }
 
     /// Commit events to GenesisDB
     ///
+    /// Returns the ids and times the events were stored with, as far as the
+    /// server reports them. Set [`CommitEvent::id`] to assign ids on the
+    /// client side.
+    ///
+    /// Transient failures are only retried when a precondition rules out
+    /// writing the events twice, i.e. an `isSubjectNew` precondition for the
+    /// subject of one of the events. Use
+    /// [`commit_events_with_retry`](Client::commit_events_with_retry) for
+    /// other guards that fail once the events are stored, or
+    /// [`commit_events_idempotent`](Client::commit_events_idempotent).
+    ///
     /// # Arguments
     ///
     /// * `events` - Events to commit
@@ -241,7 +440,7 @@ impl Client {
     ///         subject: "/user/123".to_string(),
     ///         event_type: "io.genesisdb.app.user-created".to_string(),
     ///         data: json!({ "name": "John" }),
-    ///         options: None,
+    ///         ..Default::default()
     ///     }],
     ///     None,
     /// ).await?;
@@ -252,20 +451,78 @@ impl Client {
         &self,
         events: Vec<CommitEvent>,
         preconditions: Option<Vec<Precondition>>,
-    ) -> Result<()> {
-        let url = self.build_url("commit");
+    ) -> Result<CommitResult> {
+        // Once the events are stored, their subject is no longer new and a retry is rejected
+        let retryable = preconditions.iter().flatten().any(|precondition| match precondition {
+            Precondition::IsSubjectNew { subject } => events.iter().any(|event| &event.subject == subject),
+            _ => false,
+        });
+        self.commit_events_with_headers(events, preconditions, HeaderMap::new(), retryable)
+            .await
+    }
 
+    /// Commit events, retrying transient failures according to the retry policy
+    ///
+    /// Only use this when the preconditions fail once the events have been
+    /// stored, e.g. an `isQueryResultTrue` guard on the exact number of
+    /// events of the subject the events are written to. Otherwise a retry
+    /// after a lost response writes the events twice.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - Events to commit
+    /// * `preconditions` - Preconditions that exclude committing the events twice
+    pub async fn commit_events_with_retry(
+        &self,
+        events: Vec<CommitEvent>,
+        preconditions: Vec<Precondition>,
+    ) -> Result<CommitResult> {
+        self.commit_events_with_headers(events, Some(preconditions), HeaderMap::new(), true)
+            .await
+    }
+
+    /// Commit events, sending additional request headers
+    ///
+    /// Transient failures are retried if `retryable` is set, which callers
+    /// must only do if the preconditions exclude writing the events twice.
+    pub(crate) async fn commit_events_with_headers(
+        &self,
+        events: Vec<CommitEvent>,
+        preconditions: Option<Vec<Precondition>>,
+        extra_headers: HeaderMap,
+        retryable: bool,
+    ) -> Result<CommitResult> {
         let mut headers = self.default_headers();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.extend(extra_headers);
+
+        // Rejected before sending, the request body could not be serialized
+        for event in &events {
+            let violations: Vec<String> = event
+                .extensions
+                .keys()
+                .filter_map(|name| extension_name_violation(name))
+                .collect();
+            if !violations.is_empty() {
+                return Err(Error::InvalidEvent {
+                    event_id: event.id.map(|id| id.to_string()).unwrap_or_default(),
+                    violations,
+                });
+            }
+        }
+
+        let client_ids: Vec<Option<Uuid>> = events.iter().map(|e| e.id).collect();
 
         let internal_events: Vec<CommitEventInternal> = events
             .into_iter()
             .map(|e| CommitEventInternal {
+                id: e.id,
                 source: e.source,
                 subject: e.subject,
                 event_type: e.event_type,
                 data: e.data,
                 options: e.options,
+                extensions: e.extensions,
             })
             .collect();
 
@@ -274,22 +531,23 @@ impl Client {
             preconditions,
         };
 
-        let response = self
-            .http_client
-            .post(&url)
-            .headers(headers)
-            .json(&request_body)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(Error::ApiError {
-                status: response.status().as_u16(),
-                status_text: response.status().canonical_reason().unwrap_or("Unknown").to_string(),
-            });
-        }
-
-        Ok(())
+        telemetry::commit_batch(client_ids.len());
+        OperationSpan::new(Operation::Commit)
+            .event_count(client_ids.len())
+            .run(async {
+                let response = self
+                    .send(Operation::Commit, Method::POST, "commit", retryable, |request| {
+                        request.headers(headers.clone()).json(&request_body)
+                    })
+                    .await?;
+
+                // The events are stored at this point, a body that cannot be read only loses metadata
+                let body = response.text().await.unwrap_or_default();
+                telemetry::bytes_received(body.len());
+
+                Ok(CommitResult::from_response(&body, &client_ids))
+            })
+            .await
     }
 
     /// Erase data for a subject (GDPR compliance)
@@ -298,8 +556,6 @@ impl Client {
     ///
     /// * `subject` - The subject to erase data for
     pub async fn erase_data(&self, subject: &str) -> Result<()> {
-        let url = self.build_url("erase");
-
         let mut headers = self.default_headers();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
@@ -307,21 +563,13 @@ impl Client {
             subject: subject.to_string(),
         };
 
-        let response = self
-            .http_client
-            .post(&url)
-            .headers(headers)
-            .json(&request_body)
-            .send()
+        OperationSpan::new(Operation::Erase)
+            .subject(subject)
+            .run(self.send(Operation::Erase, Method::POST, "erase", false, |request| {
+                request.headers(headers.clone()).json(&request_body)
+            }))
             .await?;
 
-        if !response.status().is_success() {
-            return Err(Error::ApiError {
-                status: response.status().as_u16(),
-                status_text: response.status().canonical_reason().unwrap_or("Unknown").to_string(),
-            });
-        }
-
         Ok(())
     }
 
@@ -348,45 +596,102 @@ impl Client {
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn q(&self, query: &str) -> Result<Vec<Value>> {
-        let url = self.build_url("q");
+    pub async fn q(&self, query: impl Into<String>) -> Result<Vec<Value>> {
+        self.q_stream(query).await?.try_collect().await
+    }
 
+    /// Execute a query and stream the result rows as they are received
+    ///
+    /// Unlike [`Client::q`], the response is not buffered, so memory usage
+    /// does not depend on the size of the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query string to execute
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::{Client, ClientConfig};
+    /// # use futures::StreamExt;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::new(ClientConfig {
+    /// #     api_url: "http://localhost:8080".to_string(),
+    /// #     api_version: "v1".to_string(),
+    /// #     auth_token: "token".to_string(),
+    /// # })?;
+    /// let mut rows = client.q_stream("FROM e IN events PROJECT INTO { id: e.id }").await?;
+    /// while let Some(row) = rows.next().await {
+    ///     println!("{}", row?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn q_stream(&self, query: impl Into<String>) -> Result<RowStream> {
+        self.q_as(query).await
+    }
+
+    /// Execute a query and stream the result rows deserialized into `T`
+    ///
+    /// A row that cannot be deserialized yields [`Error::RowParse`] with the
+    /// line of the response it was read from.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query string to execute
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::{Client, ClientConfig};
+    /// # use futures::TryStreamExt;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::new(ClientConfig {
+    /// #     api_url: "http://localhost:8080".to_string(),
+    /// #     api_version: "v1".to_string(),
+    /// #     auth_token: "token".to_string(),
+    /// # })?;
+    /// #[derive(serde::Deserialize)]
+    /// struct Row {
+    ///     id: String,
+    /// }
+    ///
+    /// let rows: Vec<Row> = client
+    ///     .q_as("FROM e IN events PROJECT INTO { id: e.id }")
+    ///     .await?
+    ///     .try_collect()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn q_as<T: DeserializeOwned + Send + 'static>(
+        &self,
+        query: impl Into<String>,
+    ) -> Result<RowStream<T>> {
         let mut headers = self.default_headers();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
         headers.insert(ACCEPT, HeaderValue::from_static("application/x-ndjson"));
 
         let request_body = QueryRequest {
-            query: query.to_string(),
+            query: query.into(),
         };
 
-        let response = self
-            .http_client
-            .post(&url)
-            .headers(headers)
-            .json(&request_body)
-            .send()
+        let span = OperationSpan::new(Operation::Query).query_length(request_body.query.len());
+        let response = span
+            .run(self.send(Operation::Query, Method::POST, "q", true, |request| {
+                request.headers(headers.clone()).json(&request_body)
+            }))
             .await?;
 
-        if !response.status().is_success() {
-            return Err(Error::ApiError {
-                status: response.status().as_u16(),
-                status_text: response.status().canonical_reason().unwrap_or("Unknown").to_string(),
-            });
-        }
-
-        let text = response.text().await?;
-
-        if text.trim().is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let results: Vec<Value> = text
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| serde_json::from_str(line))
-            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let rows = ndjson::numbered_lines(response.bytes_stream()).map(|line| {
+            let (line, text) = line?;
+            serde_json::from_str(&text).map_err(|source| {
+                telemetry::parse_failure(Operation::Query, line, &source);
+                Error::RowParse { line, source }
+            })
+        });
 
-        Ok(results)
+        Ok(Box::pin(span.stream(rows)))
     }
 
     /// Query events (alias for `q`)
@@ -394,7 +699,7 @@ impl Client {
     /// # Arguments
     ///
     /// * `query` - The query string to execute
-    pub async fn query_events(&self, query: &str) -> Result<Vec<Value>> {
+    pub async fn query_events(&self, query: impl Into<String>) -> Result<Vec<Value>> {
         self.q(query).await
     }
 
@@ -433,9 +738,7 @@ impl Client {
         &self,
         subject: &str,
         options: Option<StreamOptions>,
-    ) -> Result<Pin<Box<dyn Stream<Item = Result<CloudEvent>> + Send>>> {
-        let url = self.build_url("observe");
-
+    ) -> Result<EventStream> {
         let mut headers = self.default_headers();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
         headers.insert(ACCEPT, HeaderValue::from_static("application/x-ndjson"));
@@ -445,72 +748,40 @@ impl Client {
             options,
         };
 
-        let response = self
-            .http_client
-            .post(&url)
-            .headers(headers)
-            .json(&request_body)
-            .send()
+        let span = OperationSpan::new(Operation::Observe).subject(subject);
+        let response = span
+            .run(self.send(Operation::Observe, Method::POST, "observe", false, |request| {
+                request.headers(headers.clone()).json(&request_body)
+            }))
             .await?;
 
-        if !response.status().is_success() {
-            return Err(Error::ApiError {
-                status: response.status().as_u16(),
-                status_text: response.status().canonical_reason().unwrap_or("Unknown").to_string(),
-            });
-        }
-
-        let byte_stream = response.bytes_stream();
-
-        let event_stream = async_stream::stream! {
-            let mut buffer = String::new();
-
-            futures::pin_mut!(byte_stream);
-
-            while let Some(chunk_result) = byte_stream.next().await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let text = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&text);
-
-                        while let Some(newline_idx) = buffer.find('\n') {
-                            let line = buffer[..newline_idx].trim().to_string();
-                            buffer = buffer[newline_idx + 1..].to_string();
-
-                            if line.is_empty() {
-                                continue;
-                            }
-
-                            // Handle SSE format with "data: " prefix
-                            let json_str = if line.starts_with("data: ") {
-                                &line[6..]
-                            } else {
-                                &line
-                            };
-
-                            // Skip heartbeat messages
-                            if let Ok(parsed) = serde_json::from_str::<Value>(json_str) {
-                                if parsed.get("payload") == Some(&Value::String(String::new()))
-                                   && parsed.as_object().map(|o| o.len()) == Some(1) {
-                                    continue;
-                                }
-                            }
-
-                            match serde_json::from_str::<CloudEvent>(json_str) {
-                                Ok(event) => yield Ok(event),
-                                Err(e) => yield Err(Error::JsonError(e)),
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        yield Err(Error::RequestError(e));
-                        break;
-                    }
+        let event_stream = ndjson::numbered_lines(response.bytes_stream()).filter_map(|line| async move {
+            match line {
+                // Skip heartbeat messages
+                Ok((_, text)) if ndjson::is_heartbeat(&text) => {
+                    telemetry::heartbeat();
+                    None
                 }
+                Ok((line, text)) => Some(
+                    serde_json::from_str(&text)
+                        .inspect(|_| telemetry::event_received(Operation::Observe))
+                        .inspect_err(|e| telemetry::parse_failure(Operation::Observe, line, e))
+                        .map_err(Error::JsonError),
+                ),
+                Err(e) => Some(Err(e)),
             }
-        };
+        });
+
+        Ok(Box::pin(span.stream(event_stream)))
+    }
+}
 
-        Ok(Box::pin(event_stream))
+/// Turn a non-2xx response into the matching error
+async fn check_response(response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(Error::from_response(response).await)
     }
 }
 
@@ -583,4 +854,31 @@ mod tests {
 
         assert_eq!(client.auth_header(), "Bearer my-secret-token");
     }
+
+    #[test]
+    fn test_timeouts_per_operation() {
+        let mut timeouts = Timeouts {
+            default: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        timeouts
+            .per_operation
+            .insert(Operation::Commit, Duration::from_secs(5));
+
+        assert_eq!(timeouts.get(Operation::Ping), Some(Duration::from_secs(30)));
+        assert_eq!(timeouts.get(Operation::Commit), Some(Duration::from_secs(5)));
+        assert_eq!(timeouts.get(Operation::Observe), None);
+        assert_eq!(timeouts.get(Operation::Stream), None);
+        assert_eq!(timeouts.get(Operation::Query), None);
+
+        timeouts
+            .per_operation
+            .insert(Operation::Stream, Duration::from_secs(120));
+        assert_eq!(timeouts.get(Operation::Stream), Some(Duration::from_secs(120)));
+
+        timeouts
+            .per_operation
+            .insert(Operation::Observe, Duration::from_secs(600));
+        assert_eq!(timeouts.get(Operation::Observe), Some(Duration::from_secs(600)));
+    }
 }
@@ -0,0 +1,153 @@
+//! Typed event payloads
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::types::{CloudEvent, CommitEvent, CommitResult, Precondition, StreamOptions};
+use futures::stream::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::pin::Pin;
+
+/// A typed event payload tied to its CloudEvents `type` and `source`
+///
+/// Usually implemented with `#[derive(DomainEvent)]` (requires the `derive`
+/// feature):
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize, DomainEvent)]
+/// #[domain_event(type = "io.genesisdb.app.user-created", source = "io.genesisdb.app")]
+/// struct UserCreated {
+///     name: String,
+/// }
+/// ```
+pub trait DomainEvent: Serialize + DeserializeOwned {
+    /// CloudEvents `type` of the event
+    const EVENT_TYPE: &'static str;
+
+    /// CloudEvents `source` of the event
+    const SOURCE: &'static str;
+
+    /// Build the event to commit for the given subject
+    fn to_commit_event(&self, subject: impl Into<String>) -> Result<CommitEvent> {
+        Ok(CommitEvent {
+            source: Self::SOURCE.to_string(),
+            subject: subject.into(),
+            event_type: Self::EVENT_TYPE.to_string(),
+            data: serde_json::to_value(self)?,
+            ..Default::default()
+        })
+    }
+
+    /// Decode the payload of a CloudEvent
+    ///
+    /// Returns `None` if the event has a different type.
+    fn from_cloud_event(event: &CloudEvent) -> Result<Option<Self>> {
+        if event.event_type != Self::EVENT_TYPE {
+            return Ok(None);
+        }
+        let data = event.data.clone().unwrap_or(Value::Null);
+        Ok(Some(serde_json::from_value(data)?))
+    }
+}
+
+/// A set of events that can be committed and decoded from CloudEvents
+///
+/// Implemented by `#[derive(DomainEvent)]` for a single event type, and by
+/// `#[derive(EventSet)]` for enums whose variants each wrap a
+/// [`DomainEvent`]. An enum variant wrapping a [`CloudEvent`] and marked with
+/// `#[domain_event(unknown)]` receives events of any other type:
+///
+/// ```ignore
+/// #[derive(EventSet)]
+/// enum UserEvent {
+///     Created(UserCreated),
+///     Renamed(UserRenamed),
+///     #[domain_event(unknown)]
+///     Unknown(CloudEvent),
+/// }
+/// ```
+pub trait EventSet: Sized {
+    /// Decode a CloudEvent into the matching event
+    ///
+    /// Fails with [`Error::UnknownEventType`] if the type is not part of the
+    /// set and there is no fallback.
+    fn decode(event: &CloudEvent) -> Result<Self>;
+
+    /// Build the event to commit for the given subject
+    fn encode(&self, subject: &str) -> Result<CommitEvent>;
+}
+
+/// Build the event to commit for an unknown CloudEvent, keeping its type and data
+#[doc(hidden)]
+pub fn encode_unknown(event: &CloudEvent, subject: &str) -> CommitEvent {
+    CommitEvent {
+        source: event.source.clone(),
+        subject: subject.to_string(),
+        event_type: event.event_type.clone(),
+        data: event.data.clone().unwrap_or(Value::Null),
+        ..Default::default()
+    }
+}
+
+/// Error for a CloudEvent whose type is not part of an event set
+#[doc(hidden)]
+pub fn unknown_event_type(event: &CloudEvent) -> Error {
+    Error::UnknownEventType(event.event_type.clone())
+}
+
+impl Client {
+    /// Commit typed events to a subject
+    ///
+    /// # Arguments
+    ///
+    /// * `subject` - The subject to commit the events to
+    /// * `events` - Events to commit
+    /// * `preconditions` - Optional preconditions to check before committing
+    pub async fn commit_domain_events<E: EventSet>(
+        &self,
+        subject: &str,
+        events: &[E],
+        preconditions: Option<Vec<Precondition>>,
+    ) -> Result<CommitResult> {
+        let events = events
+            .iter()
+            .map(|event| event.encode(subject))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.commit_events(events, preconditions).await
+    }
+
+    /// Stream events for a given subject, decoded into typed events
+    ///
+    /// # Arguments
+    ///
+    /// * `subject` - The subject to stream events for
+    /// * `options` - Optional streaming options
+    pub async fn stream_domain_events<E: EventSet>(
+        &self,
+        subject: &str,
+        options: Option<StreamOptions>,
+    ) -> Result<Vec<E>> {
+        self.stream_events(subject, options)
+            .await?
+            .iter()
+            .map(E::decode)
+            .collect()
+    }
+
+    /// Observe events for a given subject, decoded into typed events
+    ///
+    /// # Arguments
+    ///
+    /// * `subject` - The subject to observe events for
+    /// * `options` - Optional streaming options
+    pub async fn observe_domain_events<E: EventSet + Send + 'static>(
+        &self,
+        subject: &str,
+        options: Option<StreamOptions>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<E>> + Send>>> {
+        let events = self.observe_events(subject, options).await?;
+        Ok(Box::pin(events.map(|event| event.and_then(|event| E::decode(&event)))))
+    }
+}
@@ -1,5 +1,10 @@
 //! Error types for the GenesisDB client
 
+use crate::types::Precondition;
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Result type for GenesisDB client operations
@@ -12,11 +17,53 @@ pub enum Error {
     #[error("Missing required configuration: {0}")]
     MissingConfig(String),
 
-    /// API error from GenesisDB server
+    /// Invalid configuration value
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// The request was not authenticated or not permitted (401, 403)
+    #[error("Unauthorized: {status} {message}")]
+    Unauthorized {
+        status: u16,
+        message: String,
+    },
+
+    /// The requested resource does not exist (404)
+    #[error("Not found: {message}")]
+    NotFound {
+        message: String,
+    },
+
+    /// A commit precondition was not met (412)
+    #[error("Precondition failed: {message}")]
+    PreconditionFailed {
+        /// The preconditions the server reported as failed, if any
+        failed: Vec<Precondition>,
+        message: String,
+    },
+
+    /// Too many requests (429)
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        /// Delay requested by the server via the `Retry-After` header
+        retry_after: Option<Duration>,
+        message: String,
+    },
+
+    /// The server failed to process the request (5xx)
+    #[error("Server error: {status} {message}")]
+    ServerError {
+        status: u16,
+        message: String,
+    },
+
+    /// Any other API error from GenesisDB server
     #[error("API Error: {status} {status_text}")]
     ApiError {
         status: u16,
         status_text: String,
+        /// Raw response body
+        body: String,
     },
 
     /// HTTP request error
@@ -27,11 +74,250 @@ pub enum Error {
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    /// Reading or writing a local file failed
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// A SQLite operation failed
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+
+    /// A query result row could not be deserialized
+    #[error("Failed to parse query result at line {line}: {source}")]
+    RowParse {
+        /// Line of the response the row was read from, starting at 1
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// A GDBQL query is not syntactically valid
+    #[error("Invalid query: {0}")]
+    InvalidQuery(#[from] crate::gdbql::ParseError),
+
+    /// A chunk of a bulk commit failed
+    ///
+    /// The chunks in the checkpoint were committed, the others were not.
+    #[error("Bulk commit failed after {} events: {source}", checkpoint.committed_events())]
+    BulkCommitFailed {
+        /// Chunks committed before the failure, to resume from
+        checkpoint: crate::bulk::BulkCheckpoint,
+        #[source]
+        source: Box<Error>,
+    },
+
+    /// An event violates the CloudEvents spec
+    #[error("Invalid event {event_id:?}: {}", violations.join("; "))]
+    InvalidEvent {
+        event_id: String,
+        /// Every rule the event violates
+        violations: Vec<String>,
+    },
+
+    /// An audit did not pass, or its result could not be determined
+    #[error("Audit failed: {}", .0.failure_summary())]
+    AuditFailed(Box<crate::audit::AuditReport>),
+
+    /// An import stopped at a batch that could not be committed
+    ///
+    /// The events before `offset` were committed, the others were not.
+    #[error("Import failed at event {offset}: {source}")]
+    ImportFailed {
+        /// Position of the first event not committed, to resume from
+        offset: usize,
+        #[source]
+        source: Box<Error>,
+    },
+
+    /// An archive is incomplete or does not match its manifest
+    #[error("Invalid archive: {0}")]
+    InvalidArchive(String),
+
     /// Invalid response from server
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
 
+    /// The event type is not part of the event set being decoded
+    #[error("Unknown event type: {0}")]
+    UnknownEventType(String),
+
     /// Environment variable error
     #[error("Environment variable error: {0}")]
     EnvError(String),
 }
+
+impl Error {
+    /// Whether the error is transient and the operation may succeed if retried
+    ///
+    /// Connection failures, rate limiting and server-side errors are
+    /// considered transient. Configuration, client and parse errors are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::RequestError(e) => !e.is_builder(),
+            Error::RateLimited { .. } => true,
+            Error::ServerError { status, .. } => *status != 501 && *status != 505,
+            _ => false,
+        }
+    }
+
+    /// HTTP status code of the response the error was created from, if any
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Unauthorized { status, .. }
+            | Error::ServerError { status, .. }
+            | Error::ApiError { status, .. } => Some(*status),
+            Error::NotFound { .. } => Some(404),
+            Error::PreconditionFailed { .. } => Some(412),
+            Error::RateLimited { .. } => Some(429),
+            Error::RequestError(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Build an error from a non-2xx response, capturing its body
+    pub(crate) async fn from_response(response: reqwest::Response) -> Self {
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        let body = response.text().await.unwrap_or_default();
+
+        Self::from_parts(status, retry_after, body)
+    }
+
+    fn from_parts(status: StatusCode, retry_after: Option<Duration>, body: String) -> Self {
+        let status_text = status.canonical_reason().unwrap_or("Unknown").to_string();
+        let json = serde_json::from_str::<Value>(&body).ok();
+        let message = json
+            .as_ref()
+            .and_then(error_message)
+            .unwrap_or_else(|| match body.trim() {
+                "" => status_text.clone(),
+                text => text.to_string(),
+            });
+
+        match status.as_u16() {
+            401 | 403 => Error::Unauthorized {
+                status: status.as_u16(),
+                message,
+            },
+            404 => Error::NotFound { message },
+            412 => Error::PreconditionFailed {
+                failed: json.as_ref().map(failed_preconditions).unwrap_or_default(),
+                message,
+            },
+            429 => Error::RateLimited {
+                retry_after,
+                message,
+            },
+            500..=599 => Error::ServerError {
+                status: status.as_u16(),
+                message,
+            },
+            _ => Error::ApiError {
+                status: status.as_u16(),
+                status_text,
+                body,
+            },
+        }
+    }
+}
+
+/// Extract a human readable message from a JSON error body
+fn error_message(json: &Value) -> Option<String> {
+    ["message", "error", "detail"]
+        .iter()
+        .find_map(|key| json.get(key).and_then(Value::as_str))
+        .map(str::to_string)
+}
+
+/// Extract the failed preconditions from a 412 response body
+fn failed_preconditions(json: &Value) -> Vec<Precondition> {
+    let list = match json {
+        Value::Array(_) => Some(json),
+        _ => ["failed", "failedPreconditions", "preconditions"]
+            .iter()
+            .find_map(|key| json.get(key).filter(|v| v.is_array())),
+    };
+
+    list.and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| serde_json::from_value(item.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precondition_failed_body() {
+        let body = r#"{
+            "message": "subject already exists",
+            "failed": [{ "type": "isSubjectNew", "payload": { "subject": "/user/1" } }]
+        }"#;
+        let error = Error::from_parts(StatusCode::PRECONDITION_FAILED, None, body.to_string());
+
+        match error {
+            Error::PreconditionFailed { failed, message } => {
+                assert_eq!(message, "subject already exists");
+                assert_eq!(failed, vec![Precondition::is_subject_new("/user/1")]);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plain_text_body() {
+        let error = Error::from_parts(StatusCode::NOT_FOUND, None, "no such subject\n".to_string());
+        assert!(matches!(error, Error::NotFound { message } if message == "no such subject"));
+    }
+
+    #[test]
+    fn test_empty_body_uses_reason_phrase() {
+        let error = Error::from_parts(StatusCode::SERVICE_UNAVAILABLE, None, String::new());
+        assert!(matches!(
+            &error,
+            Error::ServerError { status: 503, message } if message == "Service Unavailable"
+        ));
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn test_other_status_keeps_body() {
+        let error = Error::from_parts(StatusCode::BAD_REQUEST, None, "syntax error".to_string());
+        assert!(matches!(
+            error,
+            Error::ApiError { status: 400, status_text, body }
+                if status_text == "Bad Request" && body == "syntax error"
+        ));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -0,0 +1,727 @@
+//! Typed builder and parser for GDBQL queries
+//!
+//! Values are always rendered as escaped literals, so user input can be used
+//! in queries without the risk of changing their meaning. Field paths and
+//! object keys are written as is and must not come from untrusted input.
+//!
+//! # Example
+//!
+//! ```
+//! use genesisdb_io_client::gdbql::{count, field, Query};
+//! use genesisdb_io_client::Precondition;
+//!
+//! let email = "it's@example.com";
+//! let query = Query::stream("e")
+//!     .filter(field("e.data.email").eq(email))
+//!     .project(count().eq(0));
+//!
+//! assert_eq!(
+//!     query.to_string(),
+//!     r"STREAM e FROM events WHERE e.data.email == 'it\'s@example.com' MAP COUNT() == 0"
+//! );
+//!
+//! let precondition = Precondition::is_query_result_true(query);
+//! ```
+//!
+//! Calculated fields are built with `+`, `-`, `*` and `/`, e.g.
+//! `sum(field("e.data.amount")) + 500`.
+//!
+//! Query strings can be checked offline with [`parse`] or [`validate`], which
+//! report syntax errors with their line and column.
+
+use std::fmt;
+
+mod parser;
+
+pub use parser::{parse, validate, ParseError, Position, Span};
+
+/// A GDBQL query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Whether the query is written as `FROM .. IN events` or `STREAM .. FROM events`
+    pub form: QueryForm,
+    /// Name the events are bound to, e.g. `e`
+    pub variable: String,
+    /// `WHERE` condition
+    pub filter: Option<Expr>,
+    /// `GROUP BY` expressions
+    pub group_by: Vec<Expr>,
+    /// `HAVING` condition
+    pub having: Option<Expr>,
+    /// `ORDER BY` expressions
+    pub order_by: Vec<OrderBy>,
+    /// `TOP` or `LIMIT` clause
+    pub limit: Option<Limit>,
+    /// `PROJECT INTO` or `MAP` expression
+    pub projection: Option<Expr>,
+}
+
+/// Leading form of a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryForm {
+    /// `FROM e IN events`, projected with `PROJECT INTO`
+    From,
+    /// `STREAM e FROM events`, projected with `MAP`
+    Stream,
+}
+
+/// An `ORDER BY` expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub expr: Expr,
+    pub descending: bool,
+}
+
+/// Limit on the number of results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// `TOP n`
+    Top(u64),
+    /// `LIMIT n`
+    Limit(u64),
+}
+
+/// An expression in a query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// Field path such as `e.data.email`
+    Field(String),
+    /// Escaped literal value
+    Literal(Literal),
+    /// Comparison such as `a == b`
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    /// `a AND b`
+    And(Box<Expr>, Box<Expr>),
+    /// `a OR b`
+    Or(Box<Expr>, Box<Expr>),
+    /// `NOT a`
+    Not(Box<Expr>),
+    /// `a IN (b, c)`
+    In(Box<Expr>, Vec<Expr>),
+    /// `a BETWEEN b AND c`
+    Between(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// `a UNDER b`, matching a subject and everything below it
+    Under(Box<Expr>, Box<Expr>),
+    /// `a DESCENDANTS b`, matching everything below a subject
+    Descendants(Box<Expr>, Box<Expr>),
+    /// Calculation such as `a + b`
+    Arithmetic(Box<Expr>, ArithmeticOp, Box<Expr>),
+    /// Aggregate function such as `COUNT()` or `SUM(e.data.amount)`
+    Aggregate(AggregateFn, Option<Box<Expr>>),
+    /// Object such as `{ id: e.id }`
+    Object(Vec<(String, Expr)>),
+}
+
+/// Comparison operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Arithmetic operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// Aggregate function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateFn {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+/// Literal value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    String(String),
+    Number(serde_json::Number),
+    Bool(bool),
+    Null,
+}
+
+impl Query {
+    /// Start a `FROM <variable> IN events` query
+    pub fn from_events(variable: impl Into<String>) -> Self {
+        Self::new(QueryForm::From, variable.into())
+    }
+
+    /// Start a `STREAM <variable> FROM events` query
+    pub fn stream(variable: impl Into<String>) -> Self {
+        Self::new(QueryForm::Stream, variable.into())
+    }
+
+    fn new(form: QueryForm, variable: String) -> Self {
+        Self {
+            form,
+            variable,
+            filter: None,
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None,
+            projection: None,
+        }
+    }
+
+    /// Add a `WHERE` condition, combined with AND if one is already set
+    pub fn filter(mut self, condition: Expr) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(filter) => filter.and(condition),
+            None => condition,
+        });
+        self
+    }
+
+    /// Add a `GROUP BY` expression
+    pub fn group_by(mut self, expr: Expr) -> Self {
+        self.group_by.push(expr);
+        self
+    }
+
+    /// Add a `HAVING` condition, combined with AND if one is already set
+    pub fn having(mut self, condition: Expr) -> Self {
+        self.having = Some(match self.having.take() {
+            Some(having) => having.and(condition),
+            None => condition,
+        });
+        self
+    }
+
+    /// Add an ascending `ORDER BY` expression
+    pub fn order_by(mut self, expr: Expr) -> Self {
+        self.order_by.push(OrderBy {
+            expr,
+            descending: false,
+        });
+        self
+    }
+
+    /// Add a descending `ORDER BY` expression
+    pub fn order_by_desc(mut self, expr: Expr) -> Self {
+        self.order_by.push(OrderBy {
+            expr,
+            descending: true,
+        });
+        self
+    }
+
+    /// Limit the number of results with `TOP n`
+    pub fn top(mut self, n: u64) -> Self {
+        self.limit = Some(Limit::Top(n));
+        self
+    }
+
+    /// Limit the number of results with `LIMIT n`
+    pub fn limit(mut self, n: u64) -> Self {
+        self.limit = Some(Limit::Limit(n));
+        self
+    }
+
+    /// Set the `PROJECT INTO` or `MAP` expression
+    pub fn project(mut self, expr: Expr) -> Self {
+        self.projection = Some(expr);
+        self
+    }
+}
+
+/// A field path such as `e.data.email`
+pub fn field(path: impl Into<String>) -> Expr {
+    Expr::Field(path.into())
+}
+
+/// A literal value
+pub fn lit(value: impl Into<Literal>) -> Expr {
+    Expr::Literal(value.into())
+}
+
+/// An object such as `{ id: e.id }`
+pub fn object<K: Into<String>>(fields: impl IntoIterator<Item = (K, Expr)>) -> Expr {
+    Expr::Object(fields.into_iter().map(|(key, expr)| (key.into(), expr)).collect())
+}
+
+/// `COUNT()`
+pub fn count() -> Expr {
+    Expr::Aggregate(AggregateFn::Count, None)
+}
+
+/// `SUM(expr)`
+pub fn sum(expr: Expr) -> Expr {
+    Expr::Aggregate(AggregateFn::Sum, Some(Box::new(expr)))
+}
+
+/// `AVG(expr)`
+pub fn avg(expr: Expr) -> Expr {
+    Expr::Aggregate(AggregateFn::Avg, Some(Box::new(expr)))
+}
+
+/// `MIN(expr)`
+pub fn min(expr: Expr) -> Expr {
+    Expr::Aggregate(AggregateFn::Min, Some(Box::new(expr)))
+}
+
+/// `MAX(expr)`
+pub fn max(expr: Expr) -> Expr {
+    Expr::Aggregate(AggregateFn::Max, Some(Box::new(expr)))
+}
+
+impl Expr {
+    fn compare(self, op: CompareOp, other: impl Into<Expr>) -> Expr {
+        Expr::Compare(Box::new(self), op, Box::new(other.into()))
+    }
+
+    /// `self == other`
+    pub fn eq(self, other: impl Into<Expr>) -> Expr {
+        self.compare(CompareOp::Eq, other)
+    }
+
+    /// `self != other`
+    pub fn ne(self, other: impl Into<Expr>) -> Expr {
+        self.compare(CompareOp::Ne, other)
+    }
+
+    /// `self < other`
+    pub fn lt(self, other: impl Into<Expr>) -> Expr {
+        self.compare(CompareOp::Lt, other)
+    }
+
+    /// `self <= other`
+    pub fn le(self, other: impl Into<Expr>) -> Expr {
+        self.compare(CompareOp::Le, other)
+    }
+
+    /// `self > other`
+    pub fn gt(self, other: impl Into<Expr>) -> Expr {
+        self.compare(CompareOp::Gt, other)
+    }
+
+    /// `self >= other`
+    pub fn ge(self, other: impl Into<Expr>) -> Expr {
+        self.compare(CompareOp::Ge, other)
+    }
+
+    fn arithmetic(self, op: ArithmeticOp, other: impl Into<Expr>) -> Expr {
+        Expr::Arithmetic(Box::new(self), op, Box::new(other.into()))
+    }
+
+    /// `self AND other`
+    pub fn and(self, other: Expr) -> Expr {
+        Expr::And(Box::new(self), Box::new(other))
+    }
+
+    /// `self OR other`
+    pub fn or(self, other: Expr) -> Expr {
+        Expr::Or(Box::new(self), Box::new(other))
+    }
+
+    /// `self IN (values...)`
+    pub fn is_in<V: Into<Expr>>(self, values: impl IntoIterator<Item = V>) -> Expr {
+        Expr::In(Box::new(self), values.into_iter().map(Into::into).collect())
+    }
+
+    /// `self BETWEEN low AND high`
+    pub fn between(self, low: impl Into<Expr>, high: impl Into<Expr>) -> Expr {
+        Expr::Between(Box::new(self), Box::new(low.into()), Box::new(high.into()))
+    }
+
+    /// `self UNDER subject`
+    pub fn under(self, subject: impl Into<Expr>) -> Expr {
+        Expr::Under(Box::new(self), Box::new(subject.into()))
+    }
+
+    /// `self DESCENDANTS subject`
+    pub fn descendants(self, subject: impl Into<Expr>) -> Expr {
+        Expr::Descendants(Box::new(self), Box::new(subject.into()))
+    }
+
+    /// Binding strength, higher binds tighter
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(..) => 1,
+            Expr::And(..) => 2,
+            Expr::Not(_) => 3,
+            Expr::Compare(..)
+            | Expr::In(..)
+            | Expr::Between(..)
+            | Expr::Under(..)
+            | Expr::Descendants(..) => 4,
+            Expr::Arithmetic(_, op, _) => op.precedence(),
+            Expr::Field(_) | Expr::Literal(_) | Expr::Aggregate(..) | Expr::Object(_) => 7,
+        }
+    }
+}
+
+impl ArithmeticOp {
+    /// Binding strength, on the same scale as [`Expr`]
+    fn precedence(self) -> u8 {
+        match self {
+            ArithmeticOp::Add | ArithmeticOp::Sub => 5,
+            ArithmeticOp::Mul | ArithmeticOp::Div => 6,
+        }
+    }
+}
+
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    /// `NOT self`
+    fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+}
+
+macro_rules! arithmetic_ops {
+    ($($trait:ident :: $method:ident => $op:ident, $symbol:literal),* $(,)?) => {
+        $(
+            impl<T: Into<Expr>> std::ops::$trait<T> for Expr {
+                type Output = Expr;
+
+                #[doc = concat!("`self ", $symbol, " other`")]
+                fn $method(self, other: T) -> Expr {
+                    self.arithmetic(ArithmeticOp::$op, other)
+                }
+            }
+        )*
+    };
+}
+
+arithmetic_ops! {
+    Add::add => Add, "+",
+    Sub::sub => Sub, "-",
+    Mul::mul => Mul, "*",
+    Div::div => Div, "/",
+}
+
+impl From<Literal> for Expr {
+    fn from(literal: Literal) -> Self {
+        Expr::Literal(literal)
+    }
+}
+
+macro_rules! literal_from {
+    ($($ty:ty => |$value:ident| $literal:expr),* $(,)?) => {
+        $(
+            impl From<$ty> for Literal {
+                fn from($value: $ty) -> Self {
+                    $literal
+                }
+            }
+
+            impl From<$ty> for Expr {
+                fn from(value: $ty) -> Self {
+                    Expr::Literal(value.into())
+                }
+            }
+        )*
+    };
+}
+
+literal_from! {
+    &str => |value| Literal::String(value.to_string()),
+    String => |value| Literal::String(value),
+    &String => |value| Literal::String(value.clone()),
+    bool => |value| Literal::Bool(value),
+    i32 => |value| Literal::Number(value.into()),
+    i64 => |value| Literal::Number(value.into()),
+    u32 => |value| Literal::Number(value.into()),
+    u64 => |value| Literal::Number(value.into()),
+    usize => |value| Literal::Number(value.into()),
+    f64 => |value| serde_json::Number::from_f64(value).map_or(Literal::Null, Literal::Number),
+}
+
+impl From<Query> for String {
+    fn from(query: Query) -> Self {
+        query.to_string()
+    }
+}
+
+impl From<&Query> for String {
+    fn from(query: &Query) -> Self {
+        query.to_string()
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.form {
+            QueryForm::From => write!(f, "FROM {} IN events", self.variable)?,
+            QueryForm::Stream => write!(f, "STREAM {} FROM events", self.variable)?,
+        }
+        if let Some(filter) = &self.filter {
+            write!(f, " WHERE {}", filter)?;
+        }
+        if !self.group_by.is_empty() {
+            f.write_str(" GROUP BY ")?;
+            write_list(f, &self.group_by)?;
+        }
+        if let Some(having) = &self.having {
+            write!(f, " HAVING {}", having)?;
+        }
+        for (i, order) in self.order_by.iter().enumerate() {
+            f.write_str(if i == 0 { " ORDER BY " } else { ", " })?;
+            write!(f, "{}", order.expr)?;
+            if order.descending {
+                f.write_str(" DESC")?;
+            }
+        }
+        match self.limit {
+            Some(Limit::Top(n)) => write!(f, " TOP {}", n)?,
+            Some(Limit::Limit(n)) => write!(f, " LIMIT {}", n)?,
+            None => {}
+        }
+        if let Some(projection) = &self.projection {
+            match self.form {
+                QueryForm::From => write!(f, " PROJECT INTO {}", projection)?,
+                QueryForm::Stream => write!(f, " MAP {}", projection)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Field(path) => f.write_str(path),
+            Expr::Literal(literal) => write!(f, "{}", literal),
+            Expr::Compare(left, op, right) => {
+                write_operand(f, left, 5)?;
+                write!(f, " {} ", op)?;
+                write_operand(f, right, 5)
+            }
+            Expr::And(left, right) => {
+                write_operand(f, left, 2)?;
+                f.write_str(" AND ")?;
+                write_operand(f, right, 3)
+            }
+            Expr::Or(left, right) => {
+                write_operand(f, left, 1)?;
+                f.write_str(" OR ")?;
+                write_operand(f, right, 2)
+            }
+            Expr::Not(expr) => {
+                f.write_str("NOT ")?;
+                write_operand(f, expr, 3)
+            }
+            Expr::In(expr, values) => {
+                write_operand(f, expr, 5)?;
+                f.write_str(" IN (")?;
+                write_list(f, values)?;
+                f.write_str(")")
+            }
+            Expr::Between(expr, low, high) => {
+                write_operand(f, expr, 5)?;
+                f.write_str(" BETWEEN ")?;
+                write_operand(f, low, 5)?;
+                f.write_str(" AND ")?;
+                write_operand(f, high, 5)
+            }
+            Expr::Under(expr, subject) => {
+                write_operand(f, expr, 5)?;
+                f.write_str(" UNDER ")?;
+                write_operand(f, subject, 5)
+            }
+            Expr::Descendants(expr, subject) => {
+                write_operand(f, expr, 5)?;
+                f.write_str(" DESCENDANTS ")?;
+                write_operand(f, subject, 5)
+            }
+            Expr::Arithmetic(left, op, right) => {
+                write_operand(f, left, op.precedence())?;
+                write!(f, " {} ", op)?;
+                write_operand(f, right, op.precedence() + 1)
+            }
+            Expr::Aggregate(function, argument) => match argument {
+                Some(argument) => write!(f, "{}({})", function, argument),
+                None => write!(f, "{}()", function),
+            },
+            Expr::Object(fields) => {
+                f.write_str("{ ")?;
+                for (i, (key, expr)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", key, expr)?;
+                }
+                f.write_str(" }")
+            }
+        }
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        })
+    }
+}
+
+impl fmt::Display for ArithmeticOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ArithmeticOp::Add => "+",
+            ArithmeticOp::Sub => "-",
+            ArithmeticOp::Mul => "*",
+            ArithmeticOp::Div => "/",
+        })
+    }
+}
+
+impl fmt::Display for AggregateFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AggregateFn::Count => "COUNT",
+            AggregateFn::Sum => "SUM",
+            AggregateFn::Avg => "AVG",
+            AggregateFn::Min => "MIN",
+            AggregateFn::Max => "MAX",
+        })
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::String(value) => {
+                f.write_str("'")?;
+                for c in value.chars() {
+                    if c == '\'' || c == '\\' {
+                        f.write_str("\\")?;
+                    }
+                    write!(f, "{}", c)?;
+                }
+                f.write_str("'")
+            }
+            Literal::Number(value) => write!(f, "{}", value),
+            Literal::Bool(value) => write!(f, "{}", value),
+            Literal::Null => f.write_str("null"),
+        }
+    }
+}
+
+/// Write an operand, in parentheses if it binds weaker than required
+fn write_operand(f: &mut fmt::Formatter<'_>, expr: &Expr, precedence: u8) -> fmt::Result {
+    if expr.precedence() < precedence {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, exprs: &[Expr]) -> fmt::Result {
+    for (i, expr) in exprs.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}", expr)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escapes_string_literals() {
+        assert_eq!(lit("it's").to_string(), r"'it\'s'");
+        assert_eq!(lit(r"a\'b").to_string(), r"'a\\\'b'");
+        assert_eq!(lit(f64::NAN).to_string(), "null");
+        assert_eq!(lit(2.5).to_string(), "2.5");
+    }
+
+    #[test]
+    fn test_injection_stays_inside_literal() {
+        let query = Query::stream("e").filter(field("e.data.email").eq("x' OR e.id != '"));
+        assert_eq!(
+            query.to_string(),
+            r"STREAM e FROM events WHERE e.data.email == 'x\' OR e.id != \''"
+        );
+    }
+
+    #[test]
+    fn test_from_query_with_all_clauses() {
+        let query = Query::from_events("e")
+            .filter(field("e.subject").under("/conference/2024"))
+            .filter(field("e.type").is_in(["a", "b"]).or(field("e.data.n").between(1, 5)))
+            .group_by(field("e.data.ticketType"))
+            .having(count().lt(50))
+            .order_by_desc(field("e.time"))
+            .order_by(field("e.id"))
+            .top(10)
+            .project(object([("type", field("e.data.ticketType")), ("total", sum(field("e.data.price")))]));
+
+        assert_eq!(
+            query.to_string(),
+            "FROM e IN events \
+             WHERE e.subject UNDER '/conference/2024' AND (e.type IN ('a', 'b') OR e.data.n BETWEEN 1 AND 5) \
+             GROUP BY e.data.ticketType HAVING COUNT() < 50 \
+             ORDER BY e.time DESC, e.id TOP 10 \
+             PROJECT INTO { type: e.data.ticketType, total: SUM(e.data.price) }"
+        );
+    }
+
+    #[test]
+    fn test_stream_query_with_map() {
+        let query = Query::stream("e")
+            .filter(!field("e.subject").descendants("/user").and(field("e.data.active").eq(true)))
+            .limit(20)
+            .project(field("e.data"));
+
+        assert_eq!(
+            query.to_string(),
+            "STREAM e FROM events WHERE NOT (e.subject DESCENDANTS '/user' AND e.data.active == true) LIMIT 20 MAP e.data"
+        );
+    }
+
+    #[test]
+    fn test_parenthesizes_by_precedence() {
+        let a = field("e.a").eq(1);
+        let b = field("e.b").eq(2);
+        let c = field("e.c").eq(3);
+
+        assert_eq!(a.clone().or(b.clone()).and(c.clone()).to_string(), "(e.a == 1 OR e.b == 2) AND e.c == 3");
+        assert_eq!(a.clone().and(b.clone()).or(c.clone()).to_string(), "e.a == 1 AND e.b == 2 OR e.c == 3");
+        assert_eq!(a.clone().or(b.clone().or(c)).to_string(), "e.a == 1 OR (e.b == 2 OR e.c == 3)");
+        assert_eq!((!a).and(b).to_string(), "NOT e.a == 1 AND e.b == 2");
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let query = Query::stream("e")
+            .filter(field("e.subject").eq("/account/1"))
+            .project((sum(field("e.data.amount")) + 500).le(10000));
+        assert_eq!(
+            query.to_string(),
+            "STREAM e FROM events WHERE e.subject == '/account/1' MAP SUM(e.data.amount) + 500 <= 10000"
+        );
+
+        let a = field("e.a");
+        let b = field("e.b");
+        assert_eq!(((a.clone() + b.clone()) * 2).to_string(), "(e.a + e.b) * 2");
+        assert_eq!((a.clone() + b.clone() * 2).to_string(), "e.a + e.b * 2");
+        assert_eq!((a.clone() - (b.clone() - 1)).to_string(), "e.a - (e.b - 1)");
+        assert_eq!((a / b / 2).to_string(), "e.a / e.b / 2");
+        assert_eq!(
+            object([("net", field("e.data.gross") - field("e.data.tax"))]).to_string(),
+            "{ net: e.data.gross - e.data.tax }"
+        );
+    }
+}
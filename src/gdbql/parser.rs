@@ -0,0 +1,775 @@
+//! Parser turning GDBQL query strings into a [`Query`]
+
+use super::{AggregateFn, ArithmeticOp, CompareOp, Expr, Limit, Literal, OrderBy, Query, QueryForm};
+use std::fmt;
+
+/// Position in a query string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Line, starting at 1
+    pub line: usize,
+    /// Column in characters, starting at 1
+    pub column: usize,
+    /// Byte offset, starting at 0
+    pub offset: usize,
+}
+
+/// Range of a query string, `end` is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A syntax error in a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Description of the problem
+    pub message: String,
+    /// Part of the query the error refers to
+    pub span: Span,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.span.start.line, self.span.start.column
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a query string
+///
+/// # Example
+///
+/// ```
+/// use genesisdb_io_client::gdbql::{self, field, Query};
+///
+/// let query = gdbql::parse("FROM e IN events WHERE e.type == 'created' TOP 10").unwrap();
+/// assert_eq!(query, Query::from_events("e").filter(field("e.type").eq("created")).top(10));
+///
+/// let error = gdbql::parse("FROM e IN events\nWHERE e.type = 'created'").unwrap_err();
+/// assert_eq!((error.span.start.line, error.span.start.column), (2, 14));
+/// ```
+pub fn parse(query: &str) -> Result<Query, ParseError> {
+    let tokens = tokenize(query)?;
+    let end = tokens.last().map_or(
+        Position {
+            line: 1,
+            column: 1,
+            offset: 0,
+        },
+        |token| token.span.end,
+    );
+    Parser {
+        tokens,
+        position: 0,
+        variable: String::new(),
+        end,
+    }
+    .query()
+}
+
+/// Check that a query string is syntactically valid
+///
+/// Use it at startup or in tests to catch typos in embedded queries before
+/// they reach the server.
+pub fn validate(query: &str) -> Result<(), ParseError> {
+    parse(query).map(|_| ())
+}
+
+impl std::str::FromStr for Query {
+    type Err = ParseError;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        parse(query)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Str(String),
+    Num(serde_json::Number),
+    Sym(&'static str),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Word(word) => write!(f, "`{}`", word),
+            TokenKind::Str(_) => f.write_str("string"),
+            TokenKind::Num(n) => write!(f, "`{}`", n),
+            TokenKind::Sym(symbol) => write!(f, "`{}`", symbol),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 16] = [
+    "==", "!=", "<=", ">=", "<", ">", "(", ")", "{", "}", ",", ":", "+", "-", "*", "/",
+];
+
+const KEYWORDS: [&str; 30] = [
+    "FROM", "IN", "EVENTS", "STREAM", "WHERE", "AND", "OR", "NOT", "BETWEEN", "UNDER", "DESCENDANTS",
+    "GROUP", "BY", "HAVING", "ORDER", "ASC", "DESC", "TOP", "LIMIT", "PROJECT", "INTO", "MAP", "TRUE",
+    "FALSE", "NULL", "COUNT", "SUM", "AVG", "MIN", "MAX",
+];
+
+/// Character cursor tracking line and column
+struct Cursor<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    position: Position,
+}
+
+impl Cursor<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.position.offset += c.len_utf8();
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, ParseError> {
+    let mut cursor = Cursor {
+        chars: query.chars().peekable(),
+        position: Position {
+            line: 1,
+            column: 1,
+            offset: 0,
+        },
+    };
+    let mut tokens = Vec::new();
+
+    while let Some(c) = cursor.peek() {
+        let start = cursor.position;
+        if c.is_whitespace() {
+            cursor.bump();
+            continue;
+        }
+
+        let kind = if c == '\'' || c == '"' {
+            cursor.bump();
+            let mut value = String::new();
+            loop {
+                match cursor.bump() {
+                    Some('\\') => match cursor.bump() {
+                        Some(escaped) => value.push(escaped),
+                        None => return Err(error("unterminated string", start, cursor.position)),
+                    },
+                    Some(ch) if ch == c => break,
+                    Some(ch) => value.push(ch),
+                    None => return Err(error("unterminated string", start, cursor.position)),
+                }
+            }
+            TokenKind::Str(value)
+        } else if c.is_ascii_digit()
+            || (c == '-'
+                && !ends_operand(tokens.last())
+                && query[start.offset + 1..].starts_with(|d: char| d.is_ascii_digit()))
+        {
+            let mut text = String::new();
+            text.extend(cursor.bump());
+            while let Some(ch) = cursor.peek().filter(|ch| ch.is_ascii_digit() || *ch == '.') {
+                text.push(ch);
+                cursor.bump();
+            }
+            let number = if text.contains('.') {
+                text.parse().ok().and_then(serde_json::Number::from_f64)
+            } else {
+                text.parse::<i64>().ok().map(Into::into)
+            };
+            match number {
+                Some(number) => TokenKind::Num(number),
+                None => return Err(error(format!("invalid number `{}`", text), start, cursor.position)),
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(ch) = cursor.peek().filter(|ch| ch.is_alphanumeric() || *ch == '_' || *ch == '.') {
+                word.push(ch);
+                cursor.bump();
+            }
+            if word.split('.').any(str::is_empty) {
+                return Err(error(format!("invalid field path `{}`", word), start, cursor.position));
+            }
+            TokenKind::Word(word)
+        } else {
+            let rest = &query[start.offset..];
+            let symbol = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol));
+            match symbol {
+                Some(symbol) => {
+                    for _ in 0..symbol.len() {
+                        cursor.bump();
+                    }
+                    TokenKind::Sym(symbol)
+                }
+                None => {
+                    cursor.bump();
+                    let message = match c {
+                        '=' => "unexpected `=`, use `==` to compare".to_string(),
+                        _ => format!("unexpected character `{}`", c),
+                    };
+                    return Err(error(message, start, cursor.position));
+                }
+            }
+        };
+
+        tokens.push(Token {
+            kind,
+            span: Span {
+                start,
+                end: cursor.position,
+            },
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Whether a `-` after this token is a subtraction rather than a sign
+fn ends_operand(token: Option<&Token>) -> bool {
+    match token.map(|token| &token.kind) {
+        Some(TokenKind::Str(_) | TokenKind::Num(_) | TokenKind::Sym(")")) => true,
+        Some(TokenKind::Word(word)) => {
+            !is_keyword(word) || ["TRUE", "FALSE", "NULL"].iter().any(|literal| literal.eq_ignore_ascii_case(word))
+        }
+        _ => false,
+    }
+}
+
+fn error(message: impl Into<String>, start: Position, end: Position) -> ParseError {
+    ParseError {
+        message: message.into(),
+        span: Span { start, end },
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    variable: String,
+    end: Position,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Error at the current token, or at the end of the query
+    fn error_here(&self, message: impl Into<String>) -> ParseError {
+        let message = message.into();
+        match self.peek() {
+            Some(token) => error(format!("{}, found {}", message, token.kind), token.span.start, token.span.end),
+            None => error(format!("{}, found end of query", message), self.end, self.end),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Word(word), .. }) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error_here(format!("expected `{}`", keyword)))
+        }
+    }
+
+    fn symbol(&mut self, symbol: &'static str) -> bool {
+        let found = matches!(self.peek(), Some(Token { kind: TokenKind::Sym(s), .. }) if *s == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &'static str) -> Result<(), ParseError> {
+        if self.symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.error_here(format!("expected `{}`", symbol)))
+        }
+    }
+
+    fn variable(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::Word(word),
+                ..
+            }) if !word.contains('.') && !is_keyword(word) => {
+                let word = word.clone();
+                self.position += 1;
+                Ok(word)
+            }
+            _ => Err(self.error_here("expected variable name")),
+        }
+    }
+
+    fn count(&mut self) -> Result<u64, ParseError> {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::Num(n),
+                ..
+            }) if n.is_u64() => {
+                let n = n.as_u64().unwrap_or_default();
+                self.position += 1;
+                Ok(n)
+            }
+            _ => Err(self.error_here("expected a non-negative integer")),
+        }
+    }
+
+    fn query(mut self) -> Result<Query, ParseError> {
+        let form = if self.keyword("FROM") {
+            self.variable = self.variable()?;
+            self.expect_keyword("IN")?;
+            self.expect_keyword("events")?;
+            QueryForm::From
+        } else if self.keyword("STREAM") {
+            self.variable = self.variable()?;
+            self.expect_keyword("FROM")?;
+            self.expect_keyword("events")?;
+            QueryForm::Stream
+        } else {
+            return Err(self.error_here("expected `FROM` or `STREAM`"));
+        };
+
+        let mut query = Query {
+            form,
+            variable: self.variable.clone(),
+            filter: None,
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None,
+            projection: None,
+        };
+
+        if self.keyword("WHERE") {
+            query.filter = Some(self.expr()?);
+        }
+
+        if self.keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            loop {
+                query.group_by.push(self.expr()?);
+                if !self.symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        if self.keyword("HAVING") {
+            query.having = Some(self.expr()?);
+        }
+
+        if self.keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let expr = self.expr()?;
+                let descending = if self.keyword("DESC") {
+                    true
+                } else {
+                    self.keyword("ASC");
+                    false
+                };
+                query.order_by.push(OrderBy { expr, descending });
+                if !self.symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        if self.keyword("TOP") {
+            query.limit = Some(Limit::Top(self.count()?));
+        } else if self.keyword("LIMIT") {
+            query.limit = Some(Limit::Limit(self.count()?));
+        }
+
+        if self.keyword("PROJECT") {
+            self.expect_keyword("INTO")?;
+            query.projection = Some(self.expr()?);
+        } else if self.keyword("MAP") {
+            query.projection = Some(self.expr()?);
+        }
+
+        if self.peek().is_some() {
+            return Err(self.error_here("expected end of query"));
+        }
+
+        Ok(query)
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.and_expr()?;
+        while self.keyword("OR") {
+            left = left.or(self.and_expr()?);
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.not_expr()?;
+        while self.keyword("AND") {
+            left = left.and(self.not_expr()?);
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr, ParseError> {
+        if self.keyword("NOT") {
+            return Ok(!self.not_expr()?);
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Result<Expr, ParseError> {
+        let left = self.additive()?;
+
+        let op = match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::Sym("==")) => Some(CompareOp::Eq),
+            Some(TokenKind::Sym("!=")) => Some(CompareOp::Ne),
+            Some(TokenKind::Sym("<")) => Some(CompareOp::Lt),
+            Some(TokenKind::Sym("<=")) => Some(CompareOp::Le),
+            Some(TokenKind::Sym(">")) => Some(CompareOp::Gt),
+            Some(TokenKind::Sym(">=")) => Some(CompareOp::Ge),
+            _ => None,
+        };
+        if let Some(op) = op {
+            self.position += 1;
+            return Ok(Expr::Compare(Box::new(left), op, Box::new(self.additive()?)));
+        }
+
+        if self.keyword("IN") {
+            self.expect_symbol("(")?;
+            let mut values = Vec::new();
+            if !self.symbol(")") {
+                loop {
+                    values.push(self.expr()?);
+                    if self.symbol(")") {
+                        break;
+                    }
+                    self.expect_symbol(",")?;
+                }
+            }
+            return Ok(Expr::In(Box::new(left), values));
+        }
+
+        if self.keyword("BETWEEN") {
+            let low = self.additive()?;
+            self.expect_keyword("AND")?;
+            let high = self.additive()?;
+            return Ok(Expr::Between(Box::new(left), Box::new(low), Box::new(high)));
+        }
+
+        if self.keyword("UNDER") {
+            return Ok(Expr::Under(Box::new(left), Box::new(self.additive()?)));
+        }
+
+        if self.keyword("DESCENDANTS") {
+            return Ok(Expr::Descendants(Box::new(left), Box::new(self.additive()?)));
+        }
+
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.multiplicative()?;
+        loop {
+            let op = if self.symbol("+") {
+                ArithmeticOp::Add
+            } else if self.symbol("-") {
+                ArithmeticOp::Sub
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Arithmetic(Box::new(left), op, Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.primary()?;
+        loop {
+            let op = if self.symbol("*") {
+                ArithmeticOp::Mul
+            } else if self.symbol("/") {
+                ArithmeticOp::Div
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Arithmetic(Box::new(left), op, Box::new(self.primary()?));
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.error_here("expected expression"));
+        };
+
+        match token.kind {
+            TokenKind::Str(value) => {
+                self.position += 1;
+                Ok(Expr::Literal(Literal::String(value)))
+            }
+            TokenKind::Num(value) => {
+                self.position += 1;
+                Ok(Expr::Literal(Literal::Number(value)))
+            }
+            TokenKind::Sym("(") => {
+                self.position += 1;
+                let expr = self.expr()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            TokenKind::Sym("{") => {
+                self.position += 1;
+                self.object()
+            }
+            TokenKind::Word(word) => {
+                let upper = word.to_ascii_uppercase();
+                let function = match upper.as_str() {
+                    "COUNT" => Some(AggregateFn::Count),
+                    "SUM" => Some(AggregateFn::Sum),
+                    "AVG" => Some(AggregateFn::Avg),
+                    "MIN" => Some(AggregateFn::Min),
+                    "MAX" => Some(AggregateFn::Max),
+                    _ => None,
+                };
+                if let Some(function) = function {
+                    self.position += 1;
+                    self.expect_symbol("(")?;
+                    if self.symbol(")") {
+                        return Ok(Expr::Aggregate(function, None));
+                    }
+                    let argument = self.expr()?;
+                    self.expect_symbol(")")?;
+                    return Ok(Expr::Aggregate(function, Some(Box::new(argument))));
+                }
+
+                let literal = match upper.as_str() {
+                    "TRUE" => Some(Literal::Bool(true)),
+                    "FALSE" => Some(Literal::Bool(false)),
+                    "NULL" => Some(Literal::Null),
+                    _ => None,
+                };
+                if let Some(literal) = literal {
+                    self.position += 1;
+                    return Ok(Expr::Literal(literal));
+                }
+
+                if is_keyword(&word) {
+                    return Err(self.error_here("expected expression"));
+                }
+                if word.split('.').next() != Some(self.variable.as_str()) {
+                    return Err(error(
+                        format!("unknown identifier `{}`, fields start with `{}.`", word, self.variable),
+                        token.span.start,
+                        token.span.end,
+                    ));
+                }
+                self.position += 1;
+                Ok(Expr::Field(word))
+            }
+            TokenKind::Sym(_) => Err(self.error_here("expected expression")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Expr, ParseError> {
+        let mut fields = Vec::new();
+        if self.symbol("}") {
+            return Ok(Expr::Object(fields));
+        }
+        loop {
+            let key = match self.next() {
+                Some(Token {
+                    kind: TokenKind::Word(word),
+                    ..
+                }) if !word.contains('.') => word,
+                Some(Token {
+                    kind: TokenKind::Str(key),
+                    ..
+                }) => key,
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error_here("expected object key"));
+                }
+            };
+            self.expect_symbol(":")?;
+            fields.push((key, self.expr()?));
+            if self.symbol("}") {
+                break;
+            }
+            self.expect_symbol(",")?;
+        }
+        Ok(Expr::Object(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gdbql::{count, field, lit, object, sum};
+
+    fn position(error: &ParseError) -> (usize, usize) {
+        (error.span.start.line, error.span.start.column)
+    }
+
+    /// Queries written in the README, up to their closing quote or the end of the line
+    fn readme_queries() -> Vec<&'static str> {
+        let mut queries = Vec::new();
+        for line in include_str!("../../README.md").lines() {
+            let Some(start) = ["STREAM e FROM events", "FROM e IN events"]
+                .iter()
+                .find_map(|prefix| line.find(prefix))
+            else {
+                continue;
+            };
+            let (before, rest) = line.split_at(start);
+            let end = if before.ends_with("r#\"") {
+                rest.find("\"#")
+            } else if before.ends_with('"') {
+                rest.find('"')
+            } else {
+                None
+            };
+            queries.push(&rest[..end.unwrap_or(rest.len())]);
+        }
+        queries
+    }
+
+    #[test]
+    fn test_parse_readme_queries() {
+        let queries = readme_queries();
+        assert!(queries.iter().any(|query| query.contains("SUM(e.data.amount) + 500 <= 10000")));
+        for query in queries {
+            if let Err(e) = validate(query) {
+                panic!("{}: {}", query, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_arithmetic() {
+        let parsed = parse("STREAM e FROM events MAP SUM(e.data.amount) + 500 <= 10000").unwrap();
+        assert_eq!(
+            parsed,
+            Query::stream("e").project((sum(field("e.data.amount")) + 500).le(10000))
+        );
+
+        let parsed =
+            parse("FROM e IN events WHERE e.data.a - 1 * e.data.b > -2 PROJECT INTO (e.data.a - 1) / 2").unwrap();
+        assert_eq!(
+            parsed,
+            Query::from_events("e")
+                .filter((field("e.data.a") - lit(1) * field("e.data.b")).gt(-2))
+                .project((field("e.data.a") - 1) / 2)
+        );
+
+        assert_eq!(
+            parse("FROM e IN events WHERE e.data.a -1 == 0").unwrap(),
+            parse("FROM e IN events WHERE e.data.a - 1 == 0").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_matches_builder() {
+        let parsed = parse(
+            "from e in events where e.data.n between 1 and 5 or not e.type in ('a', \"b\") \
+             group by e.type having sum(e.data.n) >= 2.5 order by e.time desc, e.id top 3 \
+             project into { type: e.type, 'count': COUNT() }",
+        )
+        .unwrap();
+
+        let built = Query::from_events("e")
+            .filter(field("e.data.n").between(1, 5).or(!field("e.type").is_in(["a", "b"])))
+            .group_by(field("e.type"))
+            .having(sum(field("e.data.n")).ge(2.5))
+            .order_by_desc(field("e.time"))
+            .order_by(field("e.id"))
+            .top(3)
+            .project(object([("type", field("e.type")), ("count", count())]));
+
+        assert_eq!(parsed, built);
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let query = Query::stream("e")
+            .filter(field("e.a").eq(1).or(field("e.b").eq(lit(r"it's \ here")).or(field("e.c").eq(-3))))
+            .filter(!field("e.subject").descendants("/x").and(field("e.d").eq(true)))
+            .limit(5)
+            .project(field("e.id").ne(lit(crate::gdbql::Literal::Null)));
+
+        assert_eq!(parse(&query.to_string()).unwrap(), query);
+
+        let query = Query::stream("e").project(object([
+            ("a", field("e.a") - (field("e.b") - -3)),
+            ("b", (field("e.a") + 1) * field("e.b") / 2),
+        ]));
+        assert_eq!(parse(&query.to_string()).unwrap(), query);
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let error = parse("FROM e IN events\nWHERE e.type = 'x'").unwrap_err();
+        assert_eq!(position(&error), (2, 14));
+        assert!(error.message.contains("=="));
+
+        let error = parse("FROM e IN events WHERE x.type == 'x'").unwrap_err();
+        assert_eq!(position(&error), (1, 24));
+        assert_eq!(error.span.end.column, 30);
+
+        let error = parse("STREAM e FROM events WHERE e.type == 'x").unwrap_err();
+        assert_eq!(position(&error), (1, 38));
+        assert_eq!(error.message, "unterminated string");
+
+        let error = parse("FROM e IN events TOP").unwrap_err();
+        assert_eq!(position(&error), (1, 21));
+        assert!(error.message.ends_with("found end of query"));
+
+        let error = parse("SELECT e FROM events").unwrap_err();
+        assert_eq!(position(&error), (1, 1));
+
+        let error = parse("FROM e IN events WHERE e.a == 1 e.b").unwrap_err();
+        assert_eq!(error.to_string(), "expected end of query, found `e.b` at line 1, column 33");
+    }
+}
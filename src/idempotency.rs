@@ -0,0 +1,235 @@
+//! Idempotent commits
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::gdbql::{count, field, object, Query};
+use crate::types::{CommitEvent, CommitResult, CommittedEvent, Precondition};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use uuid::Uuid;
+
+/// Header carrying the idempotency key of a commit
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Namespace for deriving event ids from idempotency keys
+const EVENT_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6f0c_8b4e_2a51_4f3c_9d0e_7b1a_5c2d_8e94);
+
+/// Key identifying a commit across retries
+///
+/// The ids of the committed events are derived from the key, so committing
+/// the same batch with the same key again is detected instead of writing the
+/// events twice. Persist the key together with the intent to commit (e.g. in
+/// an outbox) to retry safely after a crash or timeout.
+///
+/// # Server requirements
+///
+/// Idempotent commits rely on the server
+/// - storing events under the ids assigned by the client, and
+/// - accepting the `isQueryResultTrue` precondition
+///   `STREAM e FROM events WHERE e.subject == <subject> AND e.id == <id> MAP COUNT() == 0`,
+///   where `<subject>` and `<id>` are those of the first event of the batch.
+///
+/// The subject filter keeps the guard from scanning the whole store on
+/// every commit, provided the server can look up events by subject.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    /// Generate a random key
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// The key as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Id of the event at the given position of the batch committed with this key
+    pub fn event_id(&self, index: usize) -> Uuid {
+        Uuid::new_v5(&EVENT_ID_NAMESPACE, format!("{}/{}", self.0, index).as_bytes())
+    }
+}
+
+impl Default for IdempotencyKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for IdempotencyKey {
+    fn from(key: String) -> Self {
+        Self(key)
+    }
+}
+
+impl From<&str> for IdempotencyKey {
+    fn from(key: &str) -> Self {
+        Self(key.to_string())
+    }
+}
+
+impl From<Uuid> for IdempotencyKey {
+    fn from(key: Uuid) -> Self {
+        Self(key.to_string())
+    }
+}
+
+impl Client {
+    /// Commit events at most once per idempotency key
+    ///
+    /// Events without an id get one derived from the key, and the commit is
+    /// guarded by a precondition that fails once the batch has been stored.
+    /// Because the commit is guarded, transient failures are retried
+    /// according to the retry policy. If the commit is rejected, the client
+    /// checks whether the events already exist and, if so, returns their
+    /// ids and times instead of an error. Calling this again with the same
+    /// key and events after a timeout therefore never writes twice.
+    ///
+    /// The key is also sent in the `Idempotency-Key` header. A key that is not
+    /// a valid header value is rejected with [`Error::InvalidConfig`] before
+    /// anything is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - Events to commit
+    /// * `preconditions` - Optional preconditions to check before committing
+    /// * `key` - Key identifying the batch across retries
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::{Client, ClientConfig, CommitEvent, IdempotencyKey};
+    /// # use serde_json::json;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::new(ClientConfig {
+    /// #     api_url: "http://localhost:8080".to_string(),
+    /// #     api_version: "v1".to_string(),
+    /// #     auth_token: "token".to_string(),
+    /// # })?;
+    /// let key = IdempotencyKey::new();
+    /// let events = vec![CommitEvent {
+    ///     source: "io.genesisdb.app".to_string(),
+    ///     subject: "/user/123".to_string(),
+    ///     event_type: "io.genesisdb.app.user-created".to_string(),
+    ///     data: json!({ "name": "John" }),
+    ///     ..Default::default()
+    /// }];
+    ///
+    /// let result = match client.commit_events_idempotent(events.clone(), None, &key).await {
+    ///     Err(e) if e.is_retryable() => client.commit_events_idempotent(events, None, &key).await?,
+    ///     result => result?,
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn commit_events_idempotent(
+        &self,
+        mut events: Vec<CommitEvent>,
+        preconditions: Option<Vec<Precondition>>,
+        key: &IdempotencyKey,
+    ) -> Result<CommitResult> {
+        let value = HeaderValue::from_str(key.as_str()).map_err(|_| {
+            Error::InvalidConfig(format!("idempotency key {:?} is not a valid header value", key.as_str()))
+        })?;
+        let mut headers = HeaderMap::new();
+        headers.insert(HeaderName::from_static(IDEMPOTENCY_KEY_HEADER), value);
+
+        for (index, event) in events.iter_mut().enumerate() {
+            event.id.get_or_insert_with(|| key.event_id(index));
+        }
+        let ids: Vec<Uuid> = events.iter().filter_map(|event| event.id).collect();
+        let Some(first) = events.first() else {
+            return Ok(CommitResult::default());
+        };
+
+        let guard = Precondition::is_query_result_true(
+            Query::stream("e")
+                .filter(field("e.subject").eq(&first.subject))
+                .filter(field("e.id").eq(ids[0].to_string()))
+                .project(count().eq(0)),
+        );
+        let mut preconditions = preconditions.unwrap_or_default();
+        preconditions.push(guard);
+
+        let mut subjects: Vec<String> = events.iter().map(|event| event.subject.clone()).collect();
+        subjects.sort();
+        subjects.dedup();
+
+        match self
+            .commit_events_with_headers(events, Some(preconditions), headers, true)
+            .await
+        {
+            Err(error @ (Error::PreconditionFailed { .. } | Error::ApiError { status: 409, .. })) => {
+                match self.committed_events(&subjects, &ids).await? {
+                    Some(result) => Ok(result),
+                    None => Err(error),
+                }
+            }
+            result => result,
+        }
+    }
+
+    /// Look up the stored events with the given ids in the given subjects
+    ///
+    /// Returns `None` unless all of them exist.
+    async fn committed_events(&self, subjects: &[String], ids: &[Uuid]) -> Result<Option<CommitResult>> {
+        let events = self.stored_events(subjects, ids).await?;
+        Ok(events
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .map(|events| CommitResult { events }))
+    }
+
+    /// Look up the stored events with the given ids in the given subjects
+    ///
+    /// Returns one entry per id, `None` for ids that are not stored.
+    pub(crate) async fn stored_events(
+        &self,
+        subjects: &[String],
+        ids: &[Uuid],
+    ) -> Result<Vec<Option<CommittedEvent>>> {
+        let query = Query::from_events("e")
+            .filter(field("e.subject").is_in(subjects))
+            .filter(field("e.id").is_in(ids.iter().map(|id| id.to_string())))
+            .project(object([("id", field("e.id")), ("time", field("e.time"))]));
+
+        let rows = self.q(query).await?;
+
+        Ok(ids
+            .iter()
+            .map(|id| {
+                let id = id.to_string();
+                rows.iter()
+                    .find(|row| row.get("id").and_then(Value::as_str) == Some(id.as_str()))
+                    .map(|row| {
+                        let time = row.get("time").and_then(Value::as_str).map(str::to_string);
+                        CommittedEvent::new(Some(id), time)
+                    })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_ids_are_deterministic() {
+        let key = IdempotencyKey::from("order-42");
+        assert_eq!(key.event_id(0), IdempotencyKey::from("order-42").event_id(0));
+        assert_ne!(key.event_id(0), key.event_id(1));
+        assert_ne!(key.event_id(0), IdempotencyKey::from("order-43").event_id(0));
+        assert_ne!(IdempotencyKey::new(), IdempotencyKey::new());
+    }
+}
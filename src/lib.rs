@@ -23,10 +23,59 @@
 //! }
 //! ```
 
+mod aggregate;
+mod archive;
+mod audit;
+mod builder;
+mod bulk;
+mod checkpoint;
 mod client;
+mod domain;
 mod error;
+pub mod gdbql;
+mod idempotency;
+mod ndjson;
+mod observer;
+mod projection;
+mod retry;
+mod telemetry;
 mod types;
+mod validation;
+mod verify;
 
-pub use client::{Client, ClientConfig};
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use aggregate::{Aggregate, CommandError, Loaded, Repository};
+pub use audit::{AuditCheck, AuditReport, AuditStatus};
+pub use archive::{ArchiveManifest, Compression, ExportOptions, ImportOptions, ImportSummary};
+pub use builder::ClientBuilder;
+pub use bulk::{BulkCheckpoint, BulkOptions, BulkProgress, ProgressCallback};
+pub use checkpoint::{CheckpointStore, FileCheckpointStore, InMemoryCheckpointStore};
+pub use client::{Client, ClientConfig, EventStream, Operation, RowStream};
+pub use domain::{DomainEvent, EventSet};
 pub use error::{Error, Result};
+pub use idempotency::IdempotencyKey;
+pub use observer::{ConnectionState, ObserverEvent, ObserverStream, ReconnectPolicy};
+pub use projection::{Projection, ProjectionError, ProjectionRunner};
+pub use retry::{RetryCallback, RetryEvent, RetryPolicy};
 pub use types::*;
+pub use verify::{Anomaly, AnomalyKind, IntegrityReport, IntegrityVerifier, VerifyOptions};
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::{SqliteProjection, SqliteProjector, SqliteStore};
+#[cfg(feature = "sqlite")]
+pub use rusqlite;
+
+#[cfg(feature = "derive")]
+pub use genesisdb_derive::{DomainEvent, EventSet};
+
+/// Support code for the derive macros, not part of the public API
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::domain::{encode_unknown, unknown_event_type};
+}
@@ -0,0 +1,36 @@
+//! Client metrics recorded through the `metrics` crate facade
+//!
+//! Enabled with the `metrics` feature. The metrics are recorded by whatever
+//! recorder the application installs, e.g. a Prometheus exporter.
+
+/// Requests sent, by operation and status code (`error` if no response was received)
+pub const REQUESTS_TOTAL: &str = "genesisdb_client_requests_total";
+/// Time until the response headers were received, by operation
+pub const REQUEST_DURATION_SECONDS: &str = "genesisdb_client_request_duration_seconds";
+/// Number of events per commit
+pub const COMMIT_BATCH_SIZE: &str = "genesisdb_client_commit_batch_size";
+/// Events received by `stream_events` and `observe_events`, by operation
+pub const EVENTS_RECEIVED_TOTAL: &str = "genesisdb_client_events_received_total";
+/// Reconnects of resilient observers
+pub const OBSERVE_RECONNECTS_TOTAL: &str = "genesisdb_client_observe_reconnects_total";
+/// Response lines that could not be parsed, by operation
+pub const PARSE_ERRORS_TOTAL: &str = "genesisdb_client_parse_errors_total";
+
+/// Register descriptions of the client metrics with the installed recorder
+///
+/// Call it once after installing the recorder, e.g. a Prometheus exporter,
+/// to have the metrics documented in its output.
+pub fn describe_metrics() {
+    use metrics::{describe_counter, describe_histogram, Unit};
+
+    describe_counter!(REQUESTS_TOTAL, "Requests sent to GenesisDB, by operation and status code");
+    describe_histogram!(
+        REQUEST_DURATION_SECONDS,
+        Unit::Seconds,
+        "Time until the response headers of a request were received"
+    );
+    describe_histogram!(COMMIT_BATCH_SIZE, Unit::Count, "Number of events per commit");
+    describe_counter!(EVENTS_RECEIVED_TOTAL, "Events received from GenesisDB, by operation");
+    describe_counter!(OBSERVE_RECONNECTS_TOTAL, "Reconnects of resilient observers");
+    describe_counter!(PARSE_ERRORS_TOTAL, "Response lines that could not be parsed, by operation");
+}
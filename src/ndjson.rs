@@ -0,0 +1,147 @@
+//! Incremental NDJSON decoding for streaming responses
+
+use crate::error::{Error, Result};
+use futures::stream::{Stream, StreamExt};
+use serde_json::Value;
+
+/// Split a byte stream into NDJSON lines as chunks arrive
+///
+/// Yields every non-empty line with its 1-based line number within the
+/// response, trimmed and with any SSE `data: ` prefix removed. Lines are
+/// split on raw bytes, so multi-byte characters spanning chunk boundaries
+/// are decoded correctly. A trailing line without a newline is emitted once
+/// the stream ends. The bytes received are recorded when the stream ends or
+/// fails.
+pub(crate) fn numbered_lines<S, B, E>(byte_stream: S) -> impl Stream<Item = Result<(usize, String)>> + Send
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]> + Send,
+    E: Into<Error> + Send,
+{
+    async_stream::stream! {
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut number = 0;
+        let mut bytes = 0;
+
+        futures::pin_mut!(byte_stream);
+
+        while let Some(chunk_result) = byte_stream.next().await {
+            match chunk_result {
+                Ok(chunk) => {
+                    bytes += chunk.as_ref().len();
+                    buffer.extend_from_slice(chunk.as_ref());
+
+                    while let Some(newline_idx) = buffer.iter().position(|b| *b == b'\n') {
+                        let raw: Vec<u8> = buffer.drain(..=newline_idx).collect();
+                        number += 1;
+
+                        if let Some(line) = decode_line(&raw[..raw.len() - 1]) {
+                            yield Ok((number, line));
+                        }
+                    }
+                }
+                Err(e) => {
+                    crate::telemetry::bytes_received(bytes);
+                    yield Err(e.into());
+                    return;
+                }
+            }
+        }
+
+        crate::telemetry::bytes_received(bytes);
+
+        if !buffer.is_empty() {
+            if let Some(line) = decode_line(&buffer) {
+                yield Ok((number + 1, line));
+            }
+        }
+    }
+}
+
+fn decode_line(raw: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(raw);
+    let line = text.trim();
+
+    if line.is_empty() {
+        return None;
+    }
+
+    // Handle SSE format with "data: " prefix
+    let line = line.strip_prefix("data: ").unwrap_or(line);
+
+    Some(line.to_string())
+}
+
+/// Check whether a line is a heartbeat message sent by `/observe`
+pub(crate) fn is_heartbeat(line: &str) -> bool {
+    match serde_json::from_str::<Value>(line) {
+        Ok(Value::Object(map)) => {
+            map.len() == 1 && map.get("payload") == Some(&Value::String(String::new()))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    async fn collect(chunks: Vec<&'static str>) -> Vec<String> {
+        let byte_stream = stream::iter(
+            chunks
+                .into_iter()
+                .map(|c| Ok::<_, Error>(c.as_bytes().to_vec())),
+        );
+        numbered_lines(byte_stream)
+            .map(|line| line.unwrap().1)
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_lines_split_across_chunks() {
+        let lines = collect(vec!["{\"a\":", "1}\n{\"b\"", ":2}\n"]).await;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "{\"a\":1}");
+        assert_eq!(lines[1], "{\"b\":2}");
+    }
+
+    #[tokio::test]
+    async fn test_lines_trailing_line_and_blank_lines() {
+        let lines = collect(vec!["\n  \ndata: {\"a\":1}\n{\"b\":2}"]).await;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "{\"a\":1}");
+        assert_eq!(lines[1], "{\"b\":2}");
+    }
+
+    #[tokio::test]
+    async fn test_lines_multibyte_split() {
+        let bytes = "{\"name\":\"Jürgen\"}\n".as_bytes();
+        let (head, tail) = bytes.split_at(11);
+        let byte_stream = stream::iter(vec![
+            Ok::<_, Error>(head.to_vec()),
+            Ok::<_, Error>(tail.to_vec()),
+        ]);
+        let lines: Vec<String> = numbered_lines(byte_stream).map(|l| l.unwrap().1).collect().await;
+        assert_eq!(lines[0], "{\"name\":\"Jürgen\"}");
+    }
+
+    #[tokio::test]
+    async fn test_numbered_lines_count_blank_lines() {
+        let byte_stream = stream::iter(vec![Ok::<_, Error>(b"{}\n\n{}\n{}".to_vec())]);
+        let numbers: Vec<usize> = numbered_lines(byte_stream)
+            .map(|line| line.unwrap().0)
+            .collect()
+            .await;
+        assert_eq!(numbers, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_is_heartbeat() {
+        assert!(is_heartbeat("{\"payload\":\"\"}"));
+        assert!(!is_heartbeat("{\"payload\":\"x\"}"));
+        assert!(!is_heartbeat("{\"payload\":\"\",\"id\":\"1\"}"));
+        assert!(!is_heartbeat("not json"));
+    }
+}
@@ -0,0 +1,255 @@
+//! Resilient event observation with automatic reconnects
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::types::{CloudEvent, StreamOptions};
+use futures::stream::{Stream, StreamExt};
+use std::pin::Pin;
+use std::time::Duration;
+
+/// Policy controlling how a resilient observer reconnects
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Maximum number of consecutive reconnect attempts (`None` retries forever)
+    pub max_attempts: Option<u32>,
+    /// Delay before the first reconnect attempt
+    pub initial_delay: Duration,
+    /// Upper bound for the delay between attempts
+    pub max_delay: Duration,
+    /// Factor the delay is multiplied by after each failed attempt
+    pub multiplier: f64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: None,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay to wait before the given (1-based) reconnect attempt
+    ///
+    /// Capped at `max_delay`. A negative delay from the policy fields counts
+    /// as no delay, one that is not a number as `max_delay`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = (self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_delay.as_secs_f64())
+            .max(0.0);
+        Duration::try_from_secs_f64(delay).unwrap_or(self.max_delay)
+    }
+}
+
+/// Connection state of a resilient observer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The observe request was accepted and events are being received
+    Connected,
+    /// The connection was lost and will be re-established after `delay`
+    Reconnecting {
+        /// Number of consecutive reconnect attempts, starting at 1
+        attempt: u32,
+        /// Delay before the attempt is made
+        delay: Duration,
+    },
+    /// The observer stopped reconnecting and the stream ends
+    GaveUp,
+}
+
+/// Item yielded by a resilient observer
+// Nearly every item is an event, boxing it would only add an allocation per event
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum ObserverEvent {
+    /// An event received from the server
+    Event(CloudEvent),
+    /// The connection state changed
+    StateChanged(ConnectionState),
+}
+
+/// A stream of events and connection state changes
+pub type ObserverStream = Pin<Box<dyn Stream<Item = Result<ObserverEvent>> + Send>>;
+
+impl Client {
+    /// Observe events for a given subject, reconnecting when the connection drops
+    ///
+    /// The observer remembers the id of the last delivered event and resumes
+    /// from it after a reconnect, so no events are lost or delivered twice.
+    /// Transient failures (connection errors, 429 and 5xx responses) trigger a
+    /// reconnect according to `policy`; any other error ends the stream.
+    /// Events that fail to parse are yielded as errors without reconnecting.
+    ///
+    /// # Arguments
+    ///
+    /// * `subject` - The subject to observe events for
+    /// * `options` - Optional streaming options for the initial request
+    /// * `policy` - Reconnect policy
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::{Client, ClientConfig, ObserverEvent, ReconnectPolicy};
+    /// # use futures::StreamExt;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::new(ClientConfig {
+    /// #     api_url: "http://localhost:8080".to_string(),
+    /// #     api_version: "v1".to_string(),
+    /// #     auth_token: "token".to_string(),
+    /// # })?;
+    /// let mut stream = client.observe_events_resilient("/user", None, ReconnectPolicy::default());
+    /// while let Some(item) = stream.next().await {
+    ///     match item? {
+    ///         ObserverEvent::Event(event) => println!("Event: {:?}", event),
+    ///         ObserverEvent::StateChanged(state) => println!("State: {:?}", state),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn observe_events_resilient(
+        &self,
+        subject: &str,
+        options: Option<StreamOptions>,
+        policy: ReconnectPolicy,
+    ) -> ObserverStream {
+        let client = self.clone();
+        let subject = subject.to_string();
+
+        let stream = async_stream::stream! {
+            let mut last_id: Option<String> = None;
+            let mut attempt: u32 = 0;
+
+            loop {
+                let request_options = resume_options(options.as_ref(), last_id.as_deref());
+
+                let failure = match client.observe_events(&subject, request_options).await {
+                    Ok(mut events) => {
+                        attempt = 0;
+                        yield Ok(ObserverEvent::StateChanged(ConnectionState::Connected));
+
+                        let mut failure = None;
+                        while let Some(item) = events.next().await {
+                            match item {
+                                Ok(event) => {
+                                    last_id = Some(event.id.clone());
+                                    yield Ok(ObserverEvent::Event(event));
+                                }
+                                Err(e) if e.is_retryable() => {
+                                    failure = Some(e);
+                                    break;
+                                }
+                                Err(e) => yield Err(e),
+                            }
+                        }
+                        failure
+                    }
+                    Err(e) if e.is_retryable() => Some(e),
+                    Err(e) => {
+                        yield Ok(ObserverEvent::StateChanged(ConnectionState::GaveUp));
+                        yield Err(e);
+                        return;
+                    }
+                };
+
+                attempt += 1;
+                if policy.max_attempts.is_some_and(|max| attempt > max) {
+                    yield Ok(ObserverEvent::StateChanged(ConnectionState::GaveUp));
+                    if let Some(e) = failure {
+                        yield Err(e);
+                    }
+                    return;
+                }
+
+                let delay = policy.delay(attempt);
+                crate::telemetry::reconnect(attempt, delay);
+                yield Ok(ObserverEvent::StateChanged(ConnectionState::Reconnecting {
+                    attempt,
+                    delay,
+                }));
+                tokio::time::sleep(delay).await;
+            }
+        };
+
+        Box::pin(stream)
+    }
+}
+
+/// Build the options for a (re)connect, resuming after the last delivered event
+fn resume_options(options: Option<&StreamOptions>, last_id: Option<&str>) -> Option<StreamOptions> {
+    match last_id {
+        Some(id) => Some(StreamOptions {
+            lower_bound: Some(id.to_string()),
+            include_lower_bound_event: Some(false),
+            ..options.cloned().unwrap_or_default()
+        }),
+        None => options.cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_delay_is_capped() {
+        let policy = ReconnectPolicy {
+            max_attempts: None,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            multiplier: 2.0,
+        };
+
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(400));
+        assert_eq!(policy.delay(4), Duration::from_millis(500));
+        assert_eq!(policy.delay(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_reconnect_delay_saturates_invalid_policies() {
+        let negative = ReconnectPolicy {
+            max_attempts: None,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            multiplier: -2.0,
+        };
+        assert_eq!(negative.delay(2), Duration::ZERO);
+        assert_eq!(negative.delay(u32::MAX), Duration::ZERO);
+        assert_eq!(negative.delay(3), Duration::from_millis(400));
+
+        let not_a_number = ReconnectPolicy {
+            initial_delay: Duration::ZERO,
+            multiplier: f64::MAX,
+            ..negative.clone()
+        };
+        assert_eq!(not_a_number.delay(u32::MAX), Duration::from_millis(500));
+
+        let unbounded = ReconnectPolicy {
+            max_delay: Duration::MAX,
+            multiplier: 2.0,
+            ..negative
+        };
+        assert_eq!(unbounded.delay(u32::MAX), Duration::MAX);
+    }
+
+    #[test]
+    fn test_resume_options() {
+        assert!(resume_options(None, None).is_none());
+
+        let options = StreamOptions {
+            lower_bound: Some("0".to_string()),
+            include_lower_bound_event: Some(true),
+            latest_by_event_type: Some("test.type".to_string()),
+        };
+        let resumed = resume_options(Some(&options), Some("42")).unwrap();
+        assert_eq!(resumed.lower_bound.as_deref(), Some("42"));
+        assert_eq!(resumed.include_lower_bound_event, Some(false));
+        assert_eq!(resumed.latest_by_event_type.as_deref(), Some("test.type"));
+    }
+}
@@ -0,0 +1,242 @@
+//! Read-model projections that follow the event log
+
+use crate::checkpoint::CheckpointStore;
+use crate::client::Client;
+use crate::error::Error;
+use crate::observer::{ObserverEvent, ReconnectPolicy};
+use crate::types::{CloudEvent, StreamOptions};
+use futures::stream::{StreamExt, TryStreamExt};
+use std::fmt;
+use std::future::Future;
+use thiserror::Error;
+
+/// A read model built by handling events in order
+///
+/// # Example
+///
+/// ```
+/// # use genesisdb_io_client::{CloudEvent, Projection};
+/// # use std::collections::HashMap;
+/// #[derive(Default)]
+/// struct UserNames {
+///     names: HashMap<String, String>,
+/// }
+///
+/// impl Projection for UserNames {
+///     type Error = std::convert::Infallible;
+///
+///     fn name(&self) -> &str {
+///         "user-names"
+///     }
+///
+///     fn subject(&self) -> &str {
+///         "/user"
+///     }
+///
+///     async fn handle(&mut self, event: &CloudEvent) -> Result<(), Self::Error> {
+///         if let Some(name) = event.data.as_ref().and_then(|data| data["name"].as_str()) {
+///             self.names.insert(event.subject.clone(), name.to_string());
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Projection: Send {
+    /// Error returned when an event cannot be handled
+    type Error;
+
+    /// Name the checkpoint of the projection is stored under
+    fn name(&self) -> &str;
+
+    /// Subject whose events, including those of nested subjects, are projected
+    fn subject(&self) -> &str {
+        "/"
+    }
+
+    /// Update the read model with an event
+    fn handle(
+        &mut self,
+        event: &CloudEvent,
+    ) -> impl Future<Output = std::result::Result<(), Self::Error>> + Send;
+}
+
+/// Error returned by [`ProjectionRunner`]
+#[derive(Error, Debug)]
+pub enum ProjectionError<E> {
+    /// The projection failed to handle an event
+    #[error("Failed to handle event {event_id}: {source}")]
+    Handler {
+        /// Id of the event that was not handled
+        event_id: String,
+        source: E,
+    },
+
+    /// Streaming events or storing the checkpoint failed
+    #[error(transparent)]
+    Client(#[from] Error),
+}
+
+/// Runs a projection from its checkpoint, first over history, then live
+///
+/// The runner replays the events after the stored checkpoint with
+/// `stream_events`, then switches to observing new events, resuming after
+/// the last replayed one so no event is skipped or handled twice. While
+/// catching up, the checkpoint is saved every
+/// [`checkpoint_interval`](Self::checkpoint_interval) events and when the
+/// replay ends or fails; live events are checkpointed one by one. A handler
+/// failure stops the runner before the checkpoint moves past the failed
+/// event.
+///
+/// Checkpoints are saved separately from the read model, so after a crash
+/// the events since the last saved checkpoint are handled again. Handlers
+/// should be idempotent unless the store writes both in one transaction.
+///
+/// # Example
+///
+/// ```ignore
+/// let runner = ProjectionRunner::new(client, UserNames::default(), FileCheckpointStore::new("checkpoints.json"));
+/// runner.run().await?;
+/// ```
+pub struct ProjectionRunner<P, S> {
+    client: Client,
+    projection: P,
+    store: S,
+    reconnect_policy: ReconnectPolicy,
+    checkpoint_interval: u32,
+}
+
+impl<P, S: fmt::Debug> fmt::Debug for ProjectionRunner<P, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProjectionRunner")
+            .field("client", &self.client)
+            .field("store", &self.store)
+            .field("reconnect_policy", &self.reconnect_policy)
+            .field("checkpoint_interval", &self.checkpoint_interval)
+            .finish()
+    }
+}
+
+impl<P: Projection, S: CheckpointStore> ProjectionRunner<P, S> {
+    /// Create a runner that reconnects with the default policy and saves the
+    /// checkpoint every 100 events while catching up
+    pub fn new(client: Client, projection: P, store: S) -> Self {
+        Self {
+            client,
+            projection,
+            store,
+            reconnect_policy: ReconnectPolicy::default(),
+            checkpoint_interval: 100,
+        }
+    }
+
+    /// Set after how many replayed events the checkpoint is saved while catching up
+    ///
+    /// Values below 1 are treated as 1, which saves after every event.
+    pub fn checkpoint_interval(mut self, events: u32) -> Self {
+        self.checkpoint_interval = events.max(1);
+        self
+    }
+
+    /// Set how the runner reconnects when observing fails
+    pub fn reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
+
+    /// The projection
+    pub fn projection(&self) -> &P {
+        &self.projection
+    }
+
+    /// Consume the runner, returning the projection
+    pub fn into_projection(self) -> P {
+        self.projection
+    }
+
+    /// Handle all stored events after the checkpoint
+    ///
+    /// Returns the id of the last handled event, or of the stored checkpoint
+    /// if there were no new events.
+    pub async fn catch_up(&mut self) -> std::result::Result<Option<String>, ProjectionError<P::Error>> {
+        let checkpoint = self.store.load(self.projection.name()).await?;
+        let mut events = self
+            .client
+            .stream_events_iter(self.projection.subject(), resume_options(checkpoint.as_deref()))
+            .await?;
+
+        let mut last_event_id = checkpoint;
+        let mut unsaved = 0;
+        let result = loop {
+            let event = match events.try_next().await {
+                Ok(Some(event)) => event,
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e.into()),
+            };
+            if let Err(e) = self.handle(&event).await {
+                break Err(e);
+            }
+            last_event_id = Some(event.id);
+            unsaved += 1;
+
+            if unsaved >= self.checkpoint_interval {
+                self.save(last_event_id.as_deref()).await?;
+                unsaved = 0;
+            }
+        };
+
+        // Keep the progress made before the replay ended or failed
+        if unsaved > 0 {
+            self.save(last_event_id.as_deref()).await?;
+        }
+
+        result.map(|()| last_event_id)
+    }
+
+    /// Catch up, then handle new events as they are committed
+    ///
+    /// Only returns on error, including when the reconnect policy gives up.
+    pub async fn run(mut self) -> std::result::Result<(), ProjectionError<P::Error>> {
+        let last_event_id = self.catch_up().await?;
+
+        let mut events = self.client.observe_events_resilient(
+            self.projection.subject(),
+            resume_options(last_event_id.as_deref()),
+            self.reconnect_policy.clone(),
+        );
+
+        while let Some(item) = events.next().await {
+            if let ObserverEvent::Event(event) = item? {
+                self.handle(&event).await?;
+                self.save(Some(&event.id)).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle(&mut self, event: &CloudEvent) -> std::result::Result<(), ProjectionError<P::Error>> {
+        self.projection
+            .handle(event)
+            .await
+            .map_err(|source| ProjectionError::Handler {
+                event_id: event.id.clone(),
+                source,
+            })
+    }
+
+    async fn save(&self, event_id: Option<&str>) -> std::result::Result<(), ProjectionError<P::Error>> {
+        if let Some(event_id) = event_id {
+            self.store.save(self.projection.name(), event_id).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Options to read the events after the given event, or all events
+fn resume_options(last_event_id: Option<&str>) -> Option<StreamOptions> {
+    last_event_id.map(|id| StreamOptions {
+        lower_bound: Some(id.to_string()),
+        include_lower_bound_event: Some(false),
+        ..Default::default()
+    })
+}
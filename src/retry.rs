@@ -0,0 +1,215 @@
+//! Retry policy for transient request failures
+
+use crate::client::Operation;
+use crate::error::Error;
+use rand::Rng;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Callback invoked before a failed request is retried
+pub type RetryCallback = Arc<dyn Fn(&RetryEvent<'_>) + Send + Sync>;
+
+/// Information about a retry that is about to happen
+#[derive(Debug)]
+pub struct RetryEvent<'a> {
+    /// The operation being retried
+    pub operation: Operation,
+    /// Number of the attempt that failed, starting at 1
+    pub attempt: u32,
+    /// Delay before the next attempt
+    pub delay: Duration,
+    /// The error of the failed attempt
+    pub error: &'a Error,
+}
+
+/// Policy for retrying requests that failed with a transient error
+///
+/// Applied to `ping`, `audit`, `stream_events` and `q`, and to commits
+/// whose preconditions exclude writing the events twice: idempotent commits,
+/// commits with an `isSubjectNew` precondition for one of their subjects and
+/// [`Client::commit_events_with_retry`](crate::Client::commit_events_with_retry).
+/// Only errors for which [`Error::is_retryable`] returns true are retried.
+/// Clients use [`RetryPolicy::default`] unless configured otherwise.
+///
+/// # Example
+///
+/// ```
+/// # use genesisdb_io_client::RetryPolicy;
+/// # use std::sync::Arc;
+/// let policy = RetryPolicy {
+///     max_attempts: 5,
+///     on_retry: Some(Arc::new(|event| {
+///         eprintln!("retrying {:?} after {:?}: {}", event.operation, event.delay, event.error)
+///     })),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between attempts
+    pub max_backoff: Duration,
+    /// Factor the delay is multiplied by after each failed attempt
+    pub multiplier: f64,
+    /// Randomize each delay between half and the full computed value
+    pub jitter: bool,
+    /// Called before every retry
+    pub on_retry: Option<RetryCallback>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: true,
+            on_retry: None,
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("on_retry", &self.on_retry.is_some())
+            .finish()
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Whether a request that failed on the given (1-based) attempt should be retried
+    pub(crate) fn should_retry(&self, attempt: u32, error: &Error) -> bool {
+        attempt < self.max_attempts && error.is_retryable()
+    }
+
+    /// Delay before retrying after the given (1-based) attempt failed
+    ///
+    /// A `Retry-After` delay sent by the server takes precedence over the
+    /// computed backoff. Both are capped at `max_backoff`, and a negative or
+    /// NaN backoff from the policy fields counts as no delay.
+    pub(crate) fn delay(&self, attempt: u32, error: &Error) -> Duration {
+        if let Error::RateLimited {
+            retry_after: Some(retry_after),
+            ..
+        } = error
+        {
+            return (*retry_after).min(self.max_backoff);
+        }
+
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = (self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_backoff.as_secs_f64())
+            .max(0.0);
+
+        let backoff = if self.jitter {
+            backoff / 2.0 + rand::thread_rng().gen_range(0.0..=backoff / 2.0)
+        } else {
+            backoff
+        };
+
+        Duration::try_from_secs_f64(backoff).unwrap_or(self.max_backoff)
+    }
+
+    pub(crate) fn notify(&self, event: &RetryEvent<'_>) {
+        if let Some(on_retry) = &self.on_retry {
+            on_retry(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_error() -> Error {
+        Error::ServerError {
+            status: 503,
+            message: "Service Unavailable".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::default();
+        assert!(policy.should_retry(1, &server_error()));
+        assert!(policy.should_retry(2, &server_error()));
+        assert!(!policy.should_retry(3, &server_error()));
+        assert!(!policy.should_retry(1, &Error::NotFound { message: String::new() }));
+        assert!(!RetryPolicy::none().should_retry(1, &server_error()));
+    }
+
+    #[test]
+    fn test_delay_without_jitter() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            jitter: false,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.delay(1, &server_error()), Duration::from_millis(100));
+        assert_eq!(policy.delay(2, &server_error()), Duration::from_millis(200));
+        assert_eq!(policy.delay(3, &server_error()), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_delay_with_jitter_stays_in_range() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            ..Default::default()
+        };
+
+        for _ in 0..100 {
+            let delay = policy.delay(1, &server_error());
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_delay_honours_retry_after() {
+        let error = Error::RateLimited {
+            retry_after: Some(Duration::from_secs(7)),
+            message: String::new(),
+        };
+        assert_eq!(RetryPolicy::default().delay(1, &error), Duration::from_secs(7));
+
+        let error = Error::RateLimited {
+            retry_after: Some(Duration::from_secs(3600)),
+            message: String::new(),
+        };
+        assert_eq!(RetryPolicy::default().delay(1, &error), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_delay_saturates_invalid_policies() {
+        let policy = |multiplier: f64, max_backoff: Duration| RetryPolicy {
+            multiplier,
+            max_backoff,
+            jitter: false,
+            ..Default::default()
+        };
+
+        assert_eq!(policy(-2.0, Duration::from_secs(10)).delay(2, &server_error()), Duration::ZERO);
+        assert_eq!(policy(f64::NAN, Duration::from_secs(10)).delay(2, &server_error()), Duration::from_secs(10));
+        assert_eq!(policy(f64::INFINITY, Duration::MAX).delay(2, &server_error()), Duration::MAX);
+    }
+}
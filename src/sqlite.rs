@@ -0,0 +1,247 @@
+//! SQLite-backed checkpoints and read models
+//!
+//! Enabled with the `sqlite` feature. [`SqliteStore`] keeps checkpoints in a
+//! table of a SQLite database. Projections implementing [`SqliteProjection`]
+//! update their read model in the same database, and the read-model changes
+//! and the checkpoint are committed in one transaction: every event is
+//! applied exactly once, even across crashes.
+//!
+//! The `rusqlite` crate is re-exported, so read models can use the same
+//! version of it. SQLite calls run on the calling task. They are local and
+//! short, but a slow disk blocks the executor thread for their duration.
+
+use crate::checkpoint::CheckpointStore;
+use crate::client::Client;
+use crate::error::Result;
+use crate::projection::{Projection, ProjectionRunner};
+use crate::types::CloudEvent;
+use rusqlite::{Connection, OptionalExtension, Transaction};
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const CREATE_CHECKPOINTS: &str = "CREATE TABLE IF NOT EXISTS genesisdb_checkpoints (
+    projection TEXT PRIMARY KEY NOT NULL,
+    event_id TEXT NOT NULL
+)";
+
+const SAVE_CHECKPOINT: &str = "INSERT INTO genesisdb_checkpoints (projection, event_id) VALUES (?1, ?2)
+    ON CONFLICT (projection) DO UPDATE SET event_id = excluded.event_id
+    WHERE event_id <> excluded.event_id";
+
+/// A SQLite database holding checkpoints and read models
+///
+/// Checkpoints are stored in the `genesisdb_checkpoints` table, which is
+/// created when the store is opened. Clones share the same connection.
+#[derive(Clone)]
+pub struct SqliteStore {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl fmt::Debug for SqliteStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqliteStore").finish_non_exhaustive()
+    }
+}
+
+impl SqliteStore {
+    /// Open or create the database at the given path
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Create a database that only lives in memory
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Use an existing connection
+    pub fn from_connection(connection: Connection) -> Result<Self> {
+        connection.execute(CREATE_CHECKPOINTS, [])?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Run a function with the connection, e.g. to read the read model
+    pub fn with_connection<T>(&self, f: impl FnOnce(&Connection) -> T) -> T {
+        f(&self.connection.lock().unwrap())
+    }
+
+    /// Wrap a projection so that it is applied in transactions of this database
+    pub fn projector<P: SqliteProjection>(&self, projection: P) -> SqliteProjector<P> {
+        SqliteProjector {
+            store: self.clone(),
+            projection,
+        }
+    }
+
+    /// Create a runner applying the projection with checkpoints in this database
+    pub fn runner<P: SqliteProjection>(
+        &self,
+        client: Client,
+        projection: P,
+    ) -> ProjectionRunner<SqliteProjector<P>, SqliteStore> {
+        ProjectionRunner::new(client, self.projector(projection), self.clone())
+    }
+}
+
+impl CheckpointStore for SqliteStore {
+    async fn load(&self, projection: &str) -> Result<Option<String>> {
+        let connection = self.connection.lock().unwrap();
+        let event_id = connection
+            .query_row(
+                "SELECT event_id FROM genesisdb_checkpoints WHERE projection = ?1",
+                [projection],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(event_id)
+    }
+
+    async fn save(&self, projection: &str, event_id: &str) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(SAVE_CHECKPOINT, [projection, event_id])?;
+        Ok(())
+    }
+}
+
+/// A projection whose read model lives in a SQLite database
+///
+/// # Example
+///
+/// ```
+/// # use genesisdb_io_client::{CloudEvent, SqliteProjection};
+/// use genesisdb_io_client::rusqlite::{self, Transaction};
+///
+/// struct UserCount;
+///
+/// impl SqliteProjection for UserCount {
+///     type Error = rusqlite::Error;
+///
+///     fn name(&self) -> &str {
+///         "user-count"
+///     }
+///
+///     fn apply(&mut self, tx: &Transaction<'_>, event: &CloudEvent) -> Result<(), Self::Error> {
+///         if event.event_type == "io.genesisdb.app.user-created" {
+///             tx.execute("UPDATE user_count SET count = count + 1", [])?;
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait SqliteProjection: Send {
+    /// Error returned when an event cannot be applied
+    type Error: From<rusqlite::Error>;
+
+    /// Name the checkpoint of the projection is stored under
+    fn name(&self) -> &str;
+
+    /// Subject whose events, including those of nested subjects, are projected
+    fn subject(&self) -> &str {
+        "/"
+    }
+
+    /// Update the read model with an event
+    ///
+    /// The transaction is committed together with the checkpoint after this
+    /// returns `Ok`, and rolled back otherwise.
+    fn apply(&mut self, tx: &Transaction<'_>, event: &CloudEvent) -> std::result::Result<(), Self::Error>;
+}
+
+/// A [`SqliteProjection`] applied in transactions that include its checkpoint
+///
+/// Created with [`SqliteStore::projector`]. Saving the checkpoint again
+/// through the store afterwards does not write anything.
+pub struct SqliteProjector<P> {
+    store: SqliteStore,
+    projection: P,
+}
+
+impl<P> SqliteProjector<P> {
+    /// The wrapped projection
+    pub fn projection(&self) -> &P {
+        &self.projection
+    }
+}
+
+impl<P: SqliteProjection> Projection for SqliteProjector<P> {
+    type Error = P::Error;
+
+    fn name(&self) -> &str {
+        self.projection.name()
+    }
+
+    fn subject(&self) -> &str {
+        self.projection.subject()
+    }
+
+    async fn handle(&mut self, event: &CloudEvent) -> std::result::Result<(), P::Error> {
+        let mut connection = self.store.connection.lock().unwrap();
+        let tx = connection.transaction()?;
+        self.projection.apply(&tx, event)?;
+        tx.execute(SAVE_CHECKPOINT, [self.projection.name(), event.id.as_str()])?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter;
+
+    impl SqliteProjection for Counter {
+        type Error = rusqlite::Error;
+
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        fn apply(&mut self, tx: &Transaction<'_>, event: &CloudEvent) -> rusqlite::Result<()> {
+            tx.execute("UPDATE counter SET count = count + 1", [])?;
+            if event.event_type == "poison" {
+                return Err(rusqlite::Error::InvalidQuery);
+            }
+            Ok(())
+        }
+    }
+
+    fn event(id: &str, event_type: &str) -> CloudEvent {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "source": "test",
+            "subject": "/test",
+            "type": event_type,
+        }))
+        .unwrap()
+    }
+
+    fn count(store: &SqliteStore) -> i64 {
+        store.with_connection(|c| c.query_row("SELECT count FROM counter", [], |row| row.get(0)).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_read_model_and_checkpoint_commit_together() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        store.with_connection(|c| {
+            c.execute_batch("CREATE TABLE counter (count INTEGER); INSERT INTO counter VALUES (0);")
+                .unwrap()
+        });
+        let mut projector = store.projector(Counter);
+
+        projector.handle(&event("a", "created")).await.unwrap();
+        assert_eq!(count(&store), 1);
+        assert_eq!(store.load("counter").await.unwrap().as_deref(), Some("a"));
+
+        // A failing event leaves neither the read model nor the checkpoint changed
+        assert!(projector.handle(&event("b", "poison")).await.is_err());
+        assert_eq!(count(&store), 1);
+        assert_eq!(store.load("counter").await.unwrap().as_deref(), Some("a"));
+
+        store.save("counter", "c").await.unwrap();
+        assert_eq!(store.load("counter").await.unwrap().as_deref(), Some("c"));
+    }
+}
@@ -0,0 +1,253 @@
+//! Tracing and metrics instrumentation of client operations
+//!
+//! Tracing is compiled in with the `tracing` feature, metrics with the
+//! `metrics` feature; without them everything in here is a no-op. Spans
+//! only record the fields declared below and never any request header, so
+//! the auth token cannot end up in traces.
+
+use crate::client::Operation;
+use crate::error::{Error, Result};
+#[cfg(feature = "metrics")]
+use crate::metrics::*;
+use futures::stream::Stream;
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(feature = "tracing")]
+use std::pin::Pin;
+#[cfg(feature = "tracing")]
+use std::task::{Context, Poll};
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+#[cfg(feature = "tracing")]
+use tracing::{field::Empty, Instrument, Span};
+
+/// Create the span of an operation, with all fields it may record
+#[cfg(feature = "tracing")]
+macro_rules! operation_span {
+    ($name:literal, $operation:expr) => {
+        tracing::info_span!(
+            $name,
+            operation = $operation.name(),
+            subject = Empty,
+            event_count = Empty,
+            query_length = Empty,
+            status = Empty,
+            bytes_received = Empty,
+            events_received = Empty,
+            duration_ms = Empty,
+            error = Empty,
+        )
+    };
+}
+
+/// Span covering one operation, from sending the request to the end of the response
+pub(crate) struct OperationSpan {
+    #[cfg(feature = "tracing")]
+    span: Span,
+    #[cfg(feature = "tracing")]
+    start: Instant,
+}
+
+impl OperationSpan {
+    pub(crate) fn new(operation: Operation) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = operation;
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span: match operation {
+                Operation::Ping => operation_span!("genesisdb.ping", operation),
+                Operation::Audit => operation_span!("genesisdb.audit", operation),
+                Operation::Stream => operation_span!("genesisdb.stream", operation),
+                Operation::Commit => operation_span!("genesisdb.commit", operation),
+                Operation::Erase => operation_span!("genesisdb.erase", operation),
+                Operation::Query => operation_span!("genesisdb.query", operation),
+                Operation::Observe => operation_span!("genesisdb.observe", operation),
+            },
+            #[cfg(feature = "tracing")]
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn subject(self, subject: &str) -> Self {
+        #[cfg(feature = "tracing")]
+        self.span.record("subject", subject);
+        #[cfg(not(feature = "tracing"))]
+        let _ = subject;
+        self
+    }
+
+    pub(crate) fn event_count(self, count: usize) -> Self {
+        #[cfg(feature = "tracing")]
+        self.span.record("event_count", count);
+        #[cfg(not(feature = "tracing"))]
+        let _ = count;
+        self
+    }
+
+    pub(crate) fn query_length(self, length: usize) -> Self {
+        #[cfg(feature = "tracing")]
+        self.span.record("query_length", length);
+        #[cfg(not(feature = "tracing"))]
+        let _ = length;
+        self
+    }
+
+    /// Run a future within the span, recording its duration and any error
+    pub(crate) async fn run<T>(&self, future: impl Future<Output = Result<T>>) -> Result<T> {
+        #[cfg(feature = "tracing")]
+        {
+            let result = future.instrument(self.span.clone()).await;
+            self.span.record("duration_ms", self.start.elapsed().as_millis() as u64);
+            if let Err(error) = &result {
+                record_error(&self.span, error);
+            }
+            result
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        future.await
+    }
+
+    /// Keep the span open while a response stream is consumed
+    ///
+    /// Records the number of items and the total duration once the stream ends.
+    pub(crate) fn stream<S, T>(self, stream: S) -> impl Stream<Item = Result<T>> + Send
+    where
+        S: Stream<Item = Result<T>> + Send + 'static,
+    {
+        #[cfg(feature = "tracing")]
+        {
+            Traced {
+                inner: Box::pin(stream),
+                span: self.span,
+                start: self.start,
+                items: 0,
+            }
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        stream
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn record_error(span: &Span, error: &Error) {
+    span.record("error", tracing::field::display(error));
+    if let Some(status) = error.status() {
+        span.record("status", status);
+    }
+}
+
+#[cfg(feature = "tracing")]
+struct Traced<T> {
+    inner: Pin<Box<dyn Stream<Item = Result<T>> + Send>>,
+    span: Span,
+    start: Instant,
+    items: u64,
+}
+
+#[cfg(feature = "tracing")]
+impl<T> Stream for Traced<T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let _entered = this.span.enter();
+        let poll = this.inner.as_mut().poll_next(cx);
+
+        match &poll {
+            Poll::Ready(Some(Ok(_))) => this.items += 1,
+            Poll::Ready(Some(Err(error))) => record_error(&this.span, error),
+            Poll::Ready(None) => {
+                this.span.record("events_received", this.items);
+                this.span.record("duration_ms", this.start.elapsed().as_millis() as u64);
+            }
+            Poll::Pending => {}
+        }
+
+        poll
+    }
+}
+
+/// A request was answered, or failed without a response if `status` is `None`
+pub(crate) fn response(operation: Operation, status: Option<u16>, duration: Duration) {
+    #[cfg(feature = "tracing")]
+    if let Some(status) = status {
+        Span::current().record("status", status);
+    }
+
+    #[cfg(feature = "metrics")]
+    {
+        let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+        metrics::counter!(REQUESTS_TOTAL, "operation" => operation.name(), "status" => status).increment(1);
+        metrics::histogram!(REQUEST_DURATION_SECONDS, "operation" => operation.name())
+            .record(duration.as_secs_f64());
+    }
+
+    #[cfg(not(any(feature = "tracing", feature = "metrics")))]
+    let _ = (operation, status, duration);
+    #[cfg(all(feature = "tracing", not(feature = "metrics")))]
+    let _ = (operation, duration);
+}
+
+/// A batch of events is about to be committed
+pub(crate) fn commit_batch(size: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(COMMIT_BATCH_SIZE).record(size as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = size;
+}
+
+/// An event was received from a stream or an observation
+pub(crate) fn event_received(operation: Operation) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(EVENTS_RECEIVED_TOTAL, "operation" => operation.name()).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = operation;
+}
+
+/// A resilient observer is about to reconnect
+pub(crate) fn reconnect(attempt: u32, delay: Duration) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(attempt, delay_ms = delay.as_millis() as u64, "reconnecting observer");
+    #[cfg(feature = "metrics")]
+    metrics::counter!(OBSERVE_RECONNECTS_TOTAL).increment(1);
+    #[cfg(not(feature = "tracing"))]
+    let _ = (attempt, delay);
+}
+
+/// Record the size of the response body on the current operation span
+pub(crate) fn bytes_received(bytes: usize) {
+    #[cfg(feature = "tracing")]
+    Span::current().record("bytes_received", bytes as u64);
+    #[cfg(not(feature = "tracing"))]
+    let _ = bytes;
+}
+
+/// A failed request is about to be retried
+pub(crate) fn retry(attempt: u32, delay: Duration, error: &Error) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(attempt, delay_ms = delay.as_millis() as u64, %error, "retrying request");
+    #[cfg(not(feature = "tracing"))]
+    let _ = (attempt, delay, error);
+}
+
+/// A heartbeat was received while observing
+pub(crate) fn heartbeat() {
+    #[cfg(feature = "tracing")]
+    tracing::trace!("heartbeat");
+}
+
+/// A line of a response could not be parsed
+pub(crate) fn parse_failure(operation: Operation, line: usize, error: &serde_json::Error) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(line, %error, "failed to parse response line");
+    #[cfg(feature = "metrics")]
+    metrics::counter!(PARSE_ERRORS_TOTAL, "operation" => operation.name()).increment(1);
+    #[cfg(not(feature = "tracing"))]
+    let _ = (line, error);
+    #[cfg(not(feature = "metrics"))]
+    let _ = operation;
+}
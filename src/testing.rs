@@ -0,0 +1,867 @@
+//! In-memory GenesisDB server for tests
+//!
+//! [`FakeServer`] binds a local port and implements the GenesisDB HTTP API
+//! in memory, so tests can exercise commit-then-stream semantics without a
+//! real server. It supports `/status/ping`, `/status/audit`, `/commit`,
+//! `/stream`, `/observe`, `/erase` and a subset of `/q`.
+//!
+//! `/commit` keeps ids assigned by the client, rejects ids that already exist
+//! with 409 Conflict and responds with the stored events.
+//! [`FakeServer::lose_commit_responses`] simulates commits that are stored
+//! but reported as failed.
+//!
+//! `/observe` ends the response when an observer falls more than 1024 events
+//! behind, so a slow observer sees the stream end instead of silently missing
+//! events.
+//!
+//! Queries are parsed with [`gdbql::parse`], so the fake accepts exactly the
+//! queries the client-side parser accepts, and evaluated in memory. Without
+//! `GROUP BY`, a projection or `HAVING` containing an aggregate such as
+//! `COUNT()` yields a single row computed over all matching events.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use genesisdb_io_client::testing::FakeServer;
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = FakeServer::start().await?;
+//! let client = server.client();
+//! assert_eq!(client.ping().await?, "pong");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::{Client, ClientConfig};
+use crate::gdbql::{self, AggregateFn, ArithmeticOp, CompareOp, Expr, Limit, Literal, OrderBy};
+use crate::types::{CloudEvent, CommitEvent, Precondition, StreamOptions};
+use bytes::Bytes;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Interval between heartbeat messages sent on `/observe`
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Number of live events buffered per `/observe` response
+const OBSERVE_BUFFER: usize = 1024;
+
+/// In-memory GenesisDB server bound to a local port
+///
+/// The server stops when it is dropped.
+pub struct FakeServer {
+    addr: SocketAddr,
+    state: Arc<State>,
+    handle: JoinHandle<()>,
+}
+
+struct State {
+    auth_token: String,
+    events: Mutex<Vec<StoredEvent>>,
+    sender: broadcast::Sender<CloudEvent>,
+    /// Number of upcoming commits to answer with an error after storing them
+    lost_commit_responses: Mutex<usize>,
+}
+
+#[derive(Clone)]
+struct StoredEvent {
+    event: CloudEvent,
+    stored_as_reference: bool,
+}
+
+impl FakeServer {
+    /// Start a server accepting the auth token `secret`
+    pub async fn start() -> std::io::Result<Self> {
+        Self::with_token("secret").await
+    }
+
+    /// Start a server accepting the given auth token
+    pub async fn with_token(auth_token: impl Into<String>) -> std::io::Result<Self> {
+        let (sender, _) = broadcast::channel(OBSERVE_BUFFER);
+        let state = Arc::new(State {
+            auth_token: auth_token.into(),
+            events: Mutex::new(Vec::new()),
+            sender,
+            lost_commit_responses: Mutex::new(0),
+        });
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request)))
+            }
+        });
+
+        let server = hyper::Server::from_tcp(listener)
+            .map_err(std::io::Error::other)?
+            .serve(make_service);
+
+        let handle = tokio::spawn(async move {
+            let _ = server.await;
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            handle,
+        })
+    }
+
+    /// Base URL of the server (e.g. "http://127.0.0.1:49152")
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Client configuration pointing at this server
+    pub fn config(&self) -> ClientConfig {
+        ClientConfig {
+            api_url: self.url(),
+            api_version: "v1".to_string(),
+            auth_token: self.state.auth_token.clone(),
+        }
+    }
+
+    /// Client connected to this server
+    pub fn client(&self) -> Client {
+        Client::new(self.config()).expect("fake server config is valid")
+    }
+
+    /// All events stored so far, in commit order
+    pub fn events(&self) -> Vec<CloudEvent> {
+        self.state
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|stored| stored.event.clone())
+            .collect()
+    }
+
+    /// Store the events of the next `count` commits, but answer them with
+    /// 503 Service Unavailable, as if the response was lost
+    pub fn lose_commit_responses(&self, count: usize) {
+        *self.state.lost_commit_responses.lock().unwrap() = count;
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+type HttpResponse = Response<Body>;
+
+async fn handle(state: Arc<State>, request: Request<Body>) -> Result<HttpResponse, Infallible> {
+    let authorized = request
+        .headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        == Some(format!("Bearer {}", state.auth_token).as_str());
+    if !authorized {
+        return Ok(error_response(StatusCode::UNAUTHORIZED, "invalid auth token"));
+    }
+
+    // Paths look like /api/<version>/<endpoint>
+    let path = request.uri().path().to_string();
+    let endpoint = path
+        .strip_prefix("/api/")
+        .and_then(|rest| rest.split_once('/'))
+        .map(|(_, endpoint)| endpoint.to_string())
+        .unwrap_or_default();
+    let method = request.method().clone();
+
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(e) => return Ok(error_response(StatusCode::BAD_REQUEST, &e.to_string())),
+    };
+
+    let response = match (method, endpoint.as_str()) {
+        (Method::GET, "status/ping") => Response::new(Body::from("pong")),
+        (Method::GET, "status/audit") => audit(&state),
+        (Method::POST, "commit") => with_json(&body, |request| commit(&state, request)),
+        (Method::POST, "stream") => with_json(&body, |request| stream(&state, request)),
+        (Method::POST, "observe") => with_json(&body, |request| observe(&state, request)),
+        (Method::POST, "erase") => with_json(&body, |request| erase(&state, request)),
+        (Method::POST, "q") => with_json(&body, |request| query(&state, request)),
+        _ => error_response(StatusCode::NOT_FOUND, "not found"),
+    };
+
+    Ok(response)
+}
+
+fn with_json<T, F>(body: &[u8], handler: F) -> HttpResponse
+where
+    T: for<'de> Deserialize<'de>,
+    F: FnOnce(T) -> HttpResponse,
+{
+    match serde_json::from_slice(body) {
+        Ok(request) => handler(request),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> HttpResponse {
+    json_response(status, json!({ "message": message }))
+}
+
+fn json_response(status: StatusCode, body: Value) -> HttpResponse {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn ndjson_response<I: IntoIterator<Item = Value>>(rows: I) -> HttpResponse {
+    let body: String = rows.into_iter().map(|row| format!("{}\n", row)).collect();
+    let mut response = Response::new(Body::from(body));
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/x-ndjson"),
+    );
+    response
+}
+
+fn audit(state: &State) -> HttpResponse {
+    let count = state.events.lock().unwrap().len();
+    Response::new(Body::from(format!("Audit successful: {} events verified", count)))
+}
+
+#[derive(Deserialize)]
+struct CommitBody {
+    events: Vec<CommitEvent>,
+    #[serde(default)]
+    preconditions: Option<Vec<Precondition>>,
+}
+
+fn commit(state: &State, request: CommitBody) -> HttpResponse {
+    let mut events = state.events.lock().unwrap();
+
+    for precondition in request.preconditions.iter().flatten() {
+        match check_precondition(&events, precondition) {
+            Ok(true) => {}
+            Ok(false) => {
+                return json_response(
+                    StatusCode::PRECONDITION_FAILED,
+                    json!({
+                        "message": format!("precondition {} failed", precondition.precondition_type()),
+                        "failed": [precondition],
+                    }),
+                )
+            }
+            Err(message) => return error_response(StatusCode::BAD_REQUEST, &message),
+        }
+    }
+
+    let mut ids: HashSet<String> = events.iter().map(|stored| stored.event.id.clone()).collect();
+    for id in request.events.iter().filter_map(|event| event.id) {
+        if !ids.insert(id.to_string()) {
+            return error_response(StatusCode::CONFLICT, &format!("event {} already exists", id));
+        }
+    }
+
+    let mut committed = Vec::with_capacity(request.events.len());
+    for commit_event in request.events {
+        let now = chrono::Utc::now();
+        let event = CloudEvent {
+            id: commit_event.id.unwrap_or_else(uuid::Uuid::new_v4).to_string(),
+            source: commit_event.source,
+            event_type: commit_event.event_type,
+            subject: commit_event.subject,
+            time: Some(now),
+            raw_time: Some(now.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)),
+            data: Some(commit_event.data),
+            specversion: "1.0".to_string(),
+            datacontenttype: Some("application/json".to_string()),
+            dataschema: None,
+            extensions: commit_event.extensions,
+        };
+        let stored_as_reference = commit_event
+            .options
+            .and_then(|options| options.store_data_as_reference)
+            .unwrap_or(false);
+
+        events.push(StoredEvent {
+            event: event.clone(),
+            stored_as_reference,
+        });
+        let _ = state.sender.send(event.clone());
+        committed.push(event);
+    }
+
+    let mut lost = state.lost_commit_responses.lock().unwrap();
+    if *lost > 0 {
+        *lost -= 1;
+        return error_response(StatusCode::SERVICE_UNAVAILABLE, "response lost");
+    }
+    json_response(StatusCode::OK, json!(committed))
+}
+
+fn check_precondition(events: &[StoredEvent], precondition: &Precondition) -> Result<bool, String> {
+    match precondition {
+        Precondition::IsSubjectNew { subject } => {
+            Ok(!events.iter().any(|stored| &stored.event.subject == subject))
+        }
+        Precondition::IsSubjectExisting { subject } => {
+            Ok(events.iter().any(|stored| &stored.event.subject == subject))
+        }
+        Precondition::IsQueryResultTrue { query } => {
+            let rows = run_query(events, query)?;
+            Ok(rows.first().is_some_and(is_truthy))
+        }
+        Precondition::Other {
+            precondition_type, ..
+        } => Err(format!("unsupported precondition type {}", precondition_type)),
+    }
+}
+
+#[derive(Deserialize)]
+struct StreamBody {
+    subject: String,
+    #[serde(default)]
+    options: Option<StreamOptions>,
+}
+
+/// Whether an event belongs to the subject or one of its descendants
+fn subject_matches(subject: &str, event_subject: &str) -> bool {
+    let prefix = subject.trim_end_matches('/');
+    event_subject == subject
+        || prefix.is_empty()
+        || event_subject
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn select_events(events: &[StoredEvent], request: &StreamBody) -> Vec<CloudEvent> {
+    let options = request.options.clone().unwrap_or_default();
+
+    let start = match &options.lower_bound {
+        Some(lower_bound) => match events.iter().position(|s| &s.event.id == lower_bound) {
+            Some(index) if options.include_lower_bound_event == Some(true) => index,
+            Some(index) => index + 1,
+            None => events.len(),
+        },
+        None => 0,
+    };
+
+    let selected: Vec<CloudEvent> = events[start..]
+        .iter()
+        .map(|stored| &stored.event)
+        .filter(|event| subject_matches(&request.subject, &event.subject))
+        .cloned()
+        .collect();
+
+    match &options.latest_by_event_type {
+        Some(event_type) => {
+            let mut latest: Vec<CloudEvent> = Vec::new();
+            for event in selected.into_iter().filter(|e| &e.event_type == event_type) {
+                latest.retain(|e| e.subject != event.subject);
+                latest.push(event);
+            }
+            latest
+        }
+        None => selected,
+    }
+}
+
+fn stream(state: &State, request: StreamBody) -> HttpResponse {
+    let events = state.events.lock().unwrap();
+    ndjson_response(
+        select_events(&events, &request)
+            .into_iter()
+            .map(|event| serde_json::to_value(event).unwrap()),
+    )
+}
+
+fn observe(state: &State, request: StreamBody) -> HttpResponse {
+    // Subscribe while holding the lock so no event falls between history and live
+    let (history, mut receiver) = {
+        let events = state.events.lock().unwrap();
+        (select_events(&events, &request), state.sender.subscribe())
+    };
+
+    let latest_by_event_type = request
+        .options
+        .as_ref()
+        .and_then(|options| options.latest_by_event_type.clone());
+
+    let body = async_stream::stream! {
+        for event in history {
+            yield Ok::<_, Infallible>(event_line(&event));
+        }
+
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        heartbeat.tick().await;
+
+        loop {
+            tokio::select! {
+                received = receiver.recv() => match received {
+                    Ok(event) => {
+                        let type_matches = latest_by_event_type
+                            .as_ref()
+                            .is_none_or(|event_type| &event.event_type == event_type);
+                        if type_matches && subject_matches(&request.subject, &event.subject) {
+                            yield Ok(event_line(&event));
+                        }
+                    }
+                    // End the stream rather than skip events a slow observer missed
+                    Err(broadcast::error::RecvError::Lagged(_) | broadcast::error::RecvError::Closed) => break,
+                },
+                _ = heartbeat.tick() => yield Ok(Bytes::from_static(b"{\"payload\":\"\"}\n")),
+            }
+        }
+    };
+
+    let mut response = Response::new(Body::wrap_stream(body));
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/x-ndjson"),
+    );
+    response
+}
+
+fn event_line(event: &CloudEvent) -> Bytes {
+    Bytes::from(format!("{}\n", serde_json::to_string(event).unwrap()))
+}
+
+#[derive(Deserialize)]
+struct EraseBody {
+    subject: String,
+}
+
+fn erase(state: &State, request: EraseBody) -> HttpResponse {
+    let mut events = state.events.lock().unwrap();
+    for stored in events.iter_mut() {
+        if stored.stored_as_reference && stored.event.subject == request.subject {
+            stored.event.data = None;
+        }
+    }
+    Response::new(Body::empty())
+}
+
+#[derive(Deserialize)]
+struct QueryBody {
+    query: String,
+}
+
+fn query(state: &State, request: QueryBody) -> HttpResponse {
+    let events = state.events.lock().unwrap();
+    match run_query(&events, &request.query) {
+        Ok(rows) => ndjson_response(rows),
+        Err(message) => error_response(StatusCode::BAD_REQUEST, &message),
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+// --- GDBQL evaluation ---
+
+/// Events a result row is computed from: one event, or a group of events
+struct Scope<'a> {
+    row: &'a Value,
+    group: &'a [Value],
+}
+
+impl<'a> Scope<'a> {
+    fn single(row: &'a Value) -> Self {
+        Self {
+            row,
+            group: std::slice::from_ref(row),
+        }
+    }
+
+    /// Fields outside of aggregates resolve against the first event of the group
+    fn group(group: &'a [Value]) -> Self {
+        static NULL: Value = Value::Null;
+        Self {
+            row: group.first().unwrap_or(&NULL),
+            group,
+        }
+    }
+}
+
+fn run_query(events: &[StoredEvent], query: &str) -> Result<Vec<Value>, String> {
+    let query = gdbql::parse(query).map_err(|e| e.to_string())?;
+    let limit = query.limit.map(|limit| match limit {
+        Limit::Top(n) | Limit::Limit(n) => n as usize,
+    });
+
+    let rows: Vec<Value> = events
+        .iter()
+        .map(|stored| serde_json::to_value(&stored.event).unwrap())
+        .filter(|row| {
+            query
+                .filter
+                .as_ref()
+                .is_none_or(|filter| is_truthy(&evaluate(filter, &Scope::single(row))))
+        })
+        .collect();
+
+    let aggregated = !query.group_by.is_empty()
+        || query.having.is_some()
+        || query.projection.as_ref().is_some_and(has_aggregate);
+    if !aggregated {
+        let mut rows = rows;
+        sort(&mut rows, &query.order_by, |row| Scope::single(row));
+        rows.truncate(limit.unwrap_or(usize::MAX));
+        return Ok(match &query.projection {
+            Some(projection) => rows.iter().map(|row| evaluate(projection, &Scope::single(row))).collect(),
+            None => rows,
+        });
+    }
+
+    // Without GROUP BY all matching events form a single group
+    let mut groups: Vec<(Vec<Value>, Vec<Value>)> = Vec::new();
+    if query.group_by.is_empty() {
+        groups.push((Vec::new(), rows));
+    } else {
+        for row in rows {
+            let key: Vec<Value> = query.group_by.iter().map(|expr| evaluate(expr, &Scope::single(&row))).collect();
+            match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+                Some((_, group)) => group.push(row),
+                None => groups.push((key, vec![row])),
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<Value>> = groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|group| {
+            query
+                .having
+                .as_ref()
+                .is_none_or(|having| is_truthy(&evaluate(having, &Scope::group(group))))
+        })
+        .collect();
+    sort(&mut groups, &query.order_by, |group| Scope::group(group));
+    groups.truncate(limit.unwrap_or(usize::MAX));
+
+    Ok(groups
+        .iter()
+        .map(|group| match &query.projection {
+            Some(projection) => evaluate(projection, &Scope::group(group)),
+            None => group.first().cloned().unwrap_or(Value::Null),
+        })
+        .collect())
+}
+
+fn sort<T>(items: &mut [T], order_by: &[OrderBy], scope: impl Fn(&T) -> Scope<'_>) {
+    if order_by.is_empty() {
+        return;
+    }
+    items.sort_by(|a, b| {
+        let (a, b) = (scope(a), scope(b));
+        order_by
+            .iter()
+            .map(|order| {
+                let ordering = compare(&evaluate(&order.expr, &a), &evaluate(&order.expr, &b))
+                    .unwrap_or(Ordering::Equal);
+                if order.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+fn has_aggregate(expr: &Expr) -> bool {
+    match expr {
+        Expr::Aggregate(..) => true,
+        Expr::Field(_) | Expr::Literal(_) => false,
+        Expr::Not(expr) => has_aggregate(expr),
+        Expr::Compare(left, _, right)
+        | Expr::Arithmetic(left, _, right)
+        | Expr::And(left, right)
+        | Expr::Or(left, right)
+        | Expr::Under(left, right)
+        | Expr::Descendants(left, right) => has_aggregate(left) || has_aggregate(right),
+        Expr::In(expr, values) => has_aggregate(expr) || values.iter().any(has_aggregate),
+        Expr::Between(expr, low, high) => has_aggregate(expr) || has_aggregate(low) || has_aggregate(high),
+        Expr::Object(fields) => fields.iter().any(|(_, expr)| has_aggregate(expr)),
+    }
+}
+
+fn evaluate(expr: &Expr, scope: &Scope<'_>) -> Value {
+    match expr {
+        // Field paths start with the query variable, which stands for the event itself
+        Expr::Field(path) => path
+            .split('.')
+            .skip(1)
+            .try_fold(scope.row, |value, key| value.get(key))
+            .cloned()
+            .unwrap_or(Value::Null),
+        Expr::Literal(literal) => match literal {
+            Literal::String(s) => Value::String(s.clone()),
+            Literal::Number(n) => Value::Number(n.clone()),
+            Literal::Bool(b) => Value::Bool(*b),
+            Literal::Null => Value::Null,
+        },
+        Expr::Compare(left, op, right) => Value::Bool(apply_operator(
+            *op,
+            compare(&evaluate(left, scope), &evaluate(right, scope)),
+        )),
+        Expr::And(left, right) => Value::Bool(is_truthy(&evaluate(left, scope)) && is_truthy(&evaluate(right, scope))),
+        Expr::Or(left, right) => Value::Bool(is_truthy(&evaluate(left, scope)) || is_truthy(&evaluate(right, scope))),
+        Expr::Not(expr) => Value::Bool(!is_truthy(&evaluate(expr, scope))),
+        Expr::In(expr, values) => {
+            let value = evaluate(expr, scope);
+            Value::Bool(
+                values
+                    .iter()
+                    .any(|candidate| compare(&value, &evaluate(candidate, scope)) == Some(Ordering::Equal)),
+            )
+        }
+        Expr::Between(expr, low, high) => {
+            let value = evaluate(expr, scope);
+            Value::Bool(
+                apply_operator(CompareOp::Ge, compare(&value, &evaluate(low, scope)))
+                    && apply_operator(CompareOp::Le, compare(&value, &evaluate(high, scope))),
+            )
+        }
+        Expr::Under(expr, subject) => match (evaluate(expr, scope), evaluate(subject, scope)) {
+            (Value::String(value), Value::String(subject)) => Value::Bool(subject_matches(&subject, &value)),
+            _ => Value::Bool(false),
+        },
+        Expr::Descendants(expr, subject) => match (evaluate(expr, scope), evaluate(subject, scope)) {
+            (Value::String(value), Value::String(subject)) => {
+                Value::Bool(value != subject && subject_matches(&subject, &value))
+            }
+            _ => Value::Bool(false),
+        },
+        Expr::Arithmetic(left, op, right) => arithmetic(*op, &evaluate(left, scope), &evaluate(right, scope)),
+        Expr::Aggregate(function, argument) => aggregate(*function, argument.as_deref(), scope.group),
+        Expr::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, expr)| (key.clone(), evaluate(expr, scope)))
+                .collect::<Map<String, Value>>(),
+        ),
+    }
+}
+
+fn arithmetic(op: ArithmeticOp, left: &Value, right: &Value) -> Value {
+    if let (ArithmeticOp::Add, Value::String(a), Value::String(b)) = (op, left, right) {
+        return Value::String(format!("{}{}", a, b));
+    }
+    let (Some(a), Some(b)) = (left.as_number(), right.as_number()) else {
+        return Value::Null;
+    };
+    if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+        let result = match op {
+            ArithmeticOp::Add => a.checked_add(b),
+            ArithmeticOp::Sub => a.checked_sub(b),
+            ArithmeticOp::Mul => a.checked_mul(b),
+            ArithmeticOp::Div => None,
+        };
+        if let Some(result) = result {
+            return json!(result);
+        }
+    }
+    let (a, b) = (a.as_f64().unwrap_or(f64::NAN), b.as_f64().unwrap_or(f64::NAN));
+    let result = match op {
+        ArithmeticOp::Add => a + b,
+        ArithmeticOp::Sub => a - b,
+        ArithmeticOp::Mul => a * b,
+        ArithmeticOp::Div => a / b,
+    };
+    serde_json::Number::from_f64(result).map_or(Value::Null, Value::Number)
+}
+
+fn aggregate(function: AggregateFn, argument: Option<&Expr>, group: &[Value]) -> Value {
+    let values: Vec<Value> = match argument {
+        Some(argument) => group
+            .iter()
+            .map(|row| evaluate(argument, &Scope::single(row)))
+            .filter(|value| !value.is_null())
+            .collect(),
+        None => group.to_vec(),
+    };
+
+    match function {
+        AggregateFn::Count => json!(values.len()),
+        AggregateFn::Sum => values
+            .iter()
+            .fold(json!(0), |total, value| arithmetic(ArithmeticOp::Add, &total, value)),
+        AggregateFn::Avg => {
+            let numbers: Vec<f64> = values.iter().filter_map(Value::as_f64).collect();
+            if numbers.is_empty() {
+                return Value::Null;
+            }
+            let average = numbers.iter().sum::<f64>() / numbers.len() as f64;
+            serde_json::Number::from_f64(average).map_or(Value::Null, Value::Number)
+        }
+        AggregateFn::Min | AggregateFn::Max => {
+            let wanted = if function == AggregateFn::Min {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+            values
+                .into_iter()
+                .reduce(|best, value| if compare(&value, &best) == Some(wanted) { value } else { best })
+                .unwrap_or(Value::Null)
+        }
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn apply_operator(op: CompareOp, ordering: Option<Ordering>) -> bool {
+    match op {
+        CompareOp::Eq => ordering == Some(Ordering::Equal),
+        CompareOp::Ne => ordering != Some(Ordering::Equal),
+        CompareOp::Lt => ordering == Some(Ordering::Less),
+        CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        CompareOp::Gt => ordering == Some(Ordering::Greater),
+        CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(id: &str, subject: &str, event_type: &str, data: Value) -> StoredEvent {
+        let time = format!("2024-01-01T00:00:0{}Z", id);
+        StoredEvent {
+            event: CloudEvent {
+                id: id.to_string(),
+                source: "test".to_string(),
+                event_type: event_type.to_string(),
+                subject: subject.to_string(),
+                time: crate::types::parse_time(&time),
+                raw_time: Some(time),
+                data: Some(data),
+                specversion: "1.0".to_string(),
+                datacontenttype: None,
+                dataschema: None,
+                extensions: Default::default(),
+            },
+            stored_as_reference: false,
+        }
+    }
+
+    fn events() -> Vec<StoredEvent> {
+        vec![
+            stored("1", "/user/1", "created", json!({ "email": "a@example.com", "n": 1 })),
+            stored("2", "/user/2", "created", json!({ "email": "b@example.com", "n": 2 })),
+            stored("3", "/user/1", "updated", json!({ "email": "it's@example.com", "n": 3 })),
+        ]
+    }
+
+    #[test]
+    fn test_subject_matches() {
+        assert!(subject_matches("/", "/user/1"));
+        assert!(subject_matches("/user", "/user/1"));
+        assert!(subject_matches("/user/1", "/user/1"));
+        assert!(!subject_matches("/user/1", "/user/10"));
+    }
+
+    #[test]
+    fn test_query_where_order_top_project() {
+        let rows = run_query(
+            &events(),
+            "FROM e IN events WHERE e.type == 'created' ORDER BY e.data.n DESC TOP 1 PROJECT INTO { id: e.id, n: e.data.n }",
+        )
+        .unwrap();
+        assert_eq!(rows, vec![json!({ "id": "2", "n": 2 })]);
+    }
+
+    #[test]
+    fn test_query_under_or_and_escapes() {
+        let rows = run_query(
+            &events(),
+            r#"STREAM e FROM events WHERE e.subject UNDER '/user/1' AND (e.data.email == 'it\'s@example.com' OR e.data.n < 1) MAP e.id"#,
+        )
+        .unwrap();
+        assert_eq!(rows, vec![json!("3")]);
+    }
+
+    #[test]
+    fn test_query_count() {
+        let rows = run_query(
+            &events(),
+            "STREAM e FROM events WHERE e.data.email == \"a@example.com\" MAP COUNT() == 0",
+        )
+        .unwrap();
+        assert_eq!(rows, vec![json!(false)]);
+    }
+
+    #[test]
+    fn test_query_group_by_having_arithmetic() {
+        let rows = run_query(
+            &events(),
+            "FROM e IN events GROUP BY e.subject HAVING COUNT() > 1 \
+             PROJECT INTO { subject: e.subject, total: SUM(e.data.n) * 10 }",
+        )
+        .unwrap();
+        assert_eq!(rows, vec![json!({ "subject": "/user/1", "total": 40 })]);
+
+        let rows = run_query(
+            &events(),
+            "STREAM e FROM events WHERE e.subject UNDER '/user' MAP SUM(e.data.n) + 500 <= 505",
+        )
+        .unwrap();
+        assert_eq!(rows, vec![json!(false)]);
+    }
+
+    #[test]
+    fn test_query_rejected_by_parser() {
+        let error = run_query(&events(), "SELECT * FROM events").unwrap_err();
+        assert!(error.contains("line 1, column 1"), "{}", error);
+        assert!(run_query(&events(), "FROM e IN events WHERE e.type = 'created'").is_err());
+    }
+
+    #[test]
+    fn test_select_events_latest_by_event_type() {
+        let request = StreamBody {
+            subject: "/".to_string(),
+            options: Some(StreamOptions {
+                latest_by_event_type: Some("created".to_string()),
+                ..Default::default()
+            }),
+        };
+        let ids: Vec<String> = select_events(&events(), &request)
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec!["1", "2"]);
+    }
+}
@@ -1,10 +1,22 @@
 //! Types used by the GenesisDB client
 
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use crate::error::{Error, Result};
+use crate::validation::extension_name_violation;
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::{ser, Deserialize, Serialize, Serializer};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use uuid::Uuid;
 
 /// A CloudEvent as used by GenesisDB
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// `time` is parsed leniently: besides RFC 3339, timestamps without offset
+/// (taken as UTC) and with a space instead of `T` are accepted. The string
+/// the server sent is kept in `raw_time` and written back when the event is
+/// serialized, unless `time` was changed since.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "RawCloudEvent")]
 pub struct CloudEvent {
     /// Event ID
     pub id: String,
@@ -13,36 +25,135 @@ pub struct CloudEvent {
     pub source: String,
 
     /// Event type
-    #[serde(rename = "type")]
     pub event_type: String,
 
     /// Event subject
     pub subject: String,
 
-    /// Event time
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub time: Option<String>,
+    /// Event time, `None` if the event has none or it could not be parsed
+    pub time: Option<DateTime<Utc>>,
+
+    /// Event time as received
+    pub raw_time: Option<String>,
 
     /// Event data
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<Value>,
 
     /// CloudEvents spec version
-    #[serde(default = "default_spec_version")]
     pub specversion: String,
 
     /// Data content type
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub datacontenttype: Option<String>,
+
+    /// URI of the schema the data adheres to
+    pub dataschema: Option<String>,
+
+    /// Extension attributes, e.g. `traceparent` or `correlationid`
+    pub extensions: BTreeMap<String, Value>,
+}
+
+/// Time as written to the wire, the received string if it still matches `time`
+fn wire_time<'a>(time: &Option<DateTime<Utc>>, raw_time: &'a Option<String>) -> Option<Cow<'a, str>> {
+    match (time, raw_time) {
+        (Some(time), Some(raw)) if parse_time(raw) == Some(*time) => Some(Cow::Borrowed(raw)),
+        (Some(time), _) => Some(Cow::Owned(time.to_rfc3339_opts(SecondsFormat::AutoSi, true))),
+        (None, raw) => raw.as_deref().map(Cow::Borrowed),
+    }
+}
+
+/// Wire representation of a CloudEvent
+#[derive(Serialize, Deserialize)]
+struct RawCloudEvent<'a> {
+    id: Cow<'a, str>,
+    source: Cow<'a, str>,
+    #[serde(rename = "type")]
+    event_type: Cow<'a, str>,
+    subject: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<Cow<'a, Value>>,
+    #[serde(default = "default_spec_version")]
+    specversion: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    datacontenttype: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dataschema: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    extensions: Cow<'a, BTreeMap<String, Value>>,
+}
+
+fn default_spec_version() -> Cow<'static, str> {
+    Cow::Borrowed("1.0")
+}
+
+impl From<RawCloudEvent<'_>> for CloudEvent {
+    fn from(raw: RawCloudEvent<'_>) -> Self {
+        let raw_time = raw.time.map(Cow::into_owned);
+        CloudEvent {
+            id: raw.id.into_owned(),
+            source: raw.source.into_owned(),
+            event_type: raw.event_type.into_owned(),
+            subject: raw.subject.into_owned(),
+            time: raw_time.as_deref().and_then(parse_time),
+            raw_time,
+            data: raw.data.map(Cow::into_owned),
+            specversion: raw.specversion.into_owned(),
+            datacontenttype: raw.datacontenttype.map(Cow::into_owned),
+            dataschema: raw.dataschema.map(Cow::into_owned),
+            extensions: raw.extensions.into_owned(),
+        }
+    }
+}
+
+impl Serialize for CloudEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        RawCloudEvent {
+            id: Cow::Borrowed(&self.id),
+            source: Cow::Borrowed(&self.source),
+            event_type: Cow::Borrowed(&self.event_type),
+            subject: Cow::Borrowed(&self.subject),
+            time: wire_time(&self.time, &self.raw_time),
+            data: self.data.as_ref().map(Cow::Borrowed),
+            specversion: Cow::Borrowed(&self.specversion),
+            datacontenttype: self.datacontenttype.as_deref().map(Cow::Borrowed),
+            dataschema: self.dataschema.as_deref().map(Cow::Borrowed),
+            extensions: Cow::Borrowed(&self.extensions),
+        }
+        .serialize(serializer)
+    }
 }
 
-fn default_spec_version() -> String {
-    "1.0".to_string()
+/// Parse a timestamp leniently
+///
+/// Accepts RFC 3339, a space instead of `T`, offsets without colon, and
+/// timestamps without offset, which are taken as UTC.
+pub(crate) fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    let normalized = value.replacen(' ', "T", 1);
+    let normalized = normalized.replace('z', "Z");
+    if let Ok(time) = DateTime::parse_from_rfc3339(&normalized) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = DateTime::parse_from_str(&normalized, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        return Some(time.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(&normalized, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .map(|time| time.and_utc())
 }
 
 /// Event to be committed to GenesisDB
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct CommitEvent {
+    /// Event id, assigned by the server if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+
     /// Event source
     pub source: String,
 
@@ -59,10 +170,213 @@ pub struct CommitEvent {
     /// Event options
     #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<CommitEventOptions>,
+
+    /// Extension attributes, sent as top-level attributes of the event
+    ///
+    /// Serializing fails if a name is not lower-case alphanumeric or is the
+    /// name of another attribute, such as `id` or `data`.
+    #[serde(flatten, serialize_with = "serialize_extensions")]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+/// Serialize extension attributes, rejecting names that are invalid or would duplicate another attribute
+fn serialize_extensions<S: Serializer>(
+    extensions: &BTreeMap<String, Value>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    if let Some(violation) = extensions.keys().find_map(|name| extension_name_violation(name)) {
+        return Err(ser::Error::custom(violation));
+    }
+    extensions.serialize(serializer)
+}
+
+impl CommitEvent {
+    /// Set an extension attribute, e.g. to propagate a correlation id
+    ///
+    /// Returns [`Error::InvalidEvent`] if the name is not lower-case
+    /// alphanumeric or is the name of another attribute, such as `id` or
+    /// `data`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use genesisdb_io_client::CommitEvent;
+    /// # use serde_json::json;
+    /// let event = CommitEvent {
+    ///     source: "io.genesisdb.app".to_string(),
+    ///     subject: "/user/123".to_string(),
+    ///     event_type: "io.genesisdb.app.user-created".to_string(),
+    ///     data: json!({ "name": "John" }),
+    ///     ..Default::default()
+    /// }
+    /// .with_extension("correlationid", "c0ffee")?;
+    /// assert_eq!(event.extensions["correlationid"], "c0ffee");
+    /// # Ok::<(), genesisdb_io_client::Error>(())
+    /// ```
+    pub fn with_extension(mut self, name: impl Into<String>, value: impl Into<Value>) -> Result<Self> {
+        let name = name.into();
+        if let Some(violation) = extension_name_violation(&name) {
+            return Err(Error::InvalidEvent {
+                event_id: self.id.map(|id| id.to_string()).unwrap_or_default(),
+                violations: vec![violation],
+            });
+        }
+        self.extensions.insert(name, value.into());
+        Ok(self)
+    }
+}
+
+impl From<CloudEvent> for CommitEvent {
+    /// The event as it can be committed again
+    ///
+    /// Id, time and extension attributes are left out, as the server
+    /// assigns its own and may add extensions such as hashes.
+    fn from(event: CloudEvent) -> Self {
+        CommitEvent {
+            source: event.source,
+            subject: event.subject,
+            event_type: event.event_type,
+            data: event.data.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Result of committing events
+///
+/// Holds one entry per committed event, in the order they were passed to
+/// `commit_events`. Fields the server did not report are `None`, unless the
+/// id was assigned by the client.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommitResult {
+    pub events: Vec<CommittedEvent>,
+}
+
+/// Metadata of a committed event
+///
+/// `time` is parsed like [`CloudEvent::time`], the string the server sent is
+/// kept in `raw_time`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(from = "RawCommittedEvent")]
+pub struct CommittedEvent {
+    /// Id the event was stored with
+    pub id: Option<String>,
+    /// Time the event was stored at, `None` if not reported or unparsable
+    pub time: Option<DateTime<Utc>>,
+    /// Time as received
+    pub raw_time: Option<String>,
+}
+
+impl CommittedEvent {
+    /// Metadata with the time parsed from the given string
+    pub(crate) fn new(id: Option<String>, raw_time: Option<String>) -> Self {
+        Self {
+            id,
+            time: raw_time.as_deref().and_then(parse_time),
+            raw_time,
+        }
+    }
+}
+
+/// Wire representation of a committed event, e.g. in a persisted [`BulkCheckpoint`](crate::BulkCheckpoint)
+#[derive(Serialize, Deserialize)]
+struct RawCommittedEvent<'a> {
+    id: Option<Cow<'a, str>>,
+    time: Option<Cow<'a, str>>,
+}
+
+impl From<RawCommittedEvent<'_>> for CommittedEvent {
+    fn from(raw: RawCommittedEvent<'_>) -> Self {
+        CommittedEvent::new(raw.id.map(Cow::into_owned), raw.time.map(Cow::into_owned))
+    }
+}
+
+impl Serialize for CommittedEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        RawCommittedEvent {
+            id: self.id.as_deref().map(Cow::Borrowed),
+            time: wire_time(&self.time, &self.raw_time),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl CommitResult {
+    /// Id of the last committed event, e.g. to use as lower bound when streaming
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.events.last().and_then(|event| event.id.as_deref())
+    }
+
+    /// Build the result from the commit response body
+    ///
+    /// Accepts a JSON array of events, an object with an `events` array, or
+    /// NDJSON. Anything else, or a response with a different number of events,
+    /// only yields the ids assigned by the client.
+    pub(crate) fn from_response(body: &str, client_ids: &[Option<Uuid>]) -> Self {
+        let reported = parse_committed_events(body).filter(|events| events.len() == client_ids.len());
+
+        let events = client_ids
+            .iter()
+            .enumerate()
+            .map(|(i, client_id)| {
+                let reported = reported.as_ref().map(|events| &events[i]);
+                CommittedEvent::new(
+                    reported
+                        .and_then(|event| event.id.clone())
+                        .or_else(|| client_id.map(|id| id.to_string())),
+                    reported.and_then(|event| event.raw_time.clone()),
+                )
+            })
+            .collect();
+
+        Self { events }
+    }
+}
+
+fn parse_committed_events(body: &str) -> Option<Vec<CommittedEvent>> {
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let items = match serde_json::from_str::<Value>(body) {
+        Ok(Value::Array(items)) => items,
+        Ok(Value::Object(mut object)) => match object.remove("events") {
+            Some(Value::Array(items)) => items,
+            _ => vec![Value::Object(object)],
+        },
+        Ok(_) => return None,
+        Err(_) => body
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<std::result::Result<_, _>>()
+            .ok()?,
+    };
+
+    Some(
+        items
+            .iter()
+            .map(|item| {
+                CommittedEvent::new(
+                    item.get("id").and_then(json_string),
+                    item.get("time").and_then(json_string),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn json_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
 }
 
 /// Options for committing an event
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CommitEventOptions {
     /// Store data as reference for GDPR compliance
     #[serde(rename = "storeDataAsReference", skip_serializing_if = "Option::is_none")]
@@ -70,18 +384,139 @@ pub struct CommitEventOptions {
 }
 
 /// Precondition for committing events
+///
+/// Serializes to the wire format `{ "type": "...", "payload": { ... } }`.
+/// Types unknown to this client are kept as [`Precondition::Other`], so
+/// preconditions round-trip through serialization unchanged.
+///
+/// # Example
+///
+/// ```
+/// # use genesisdb_io_client::Precondition;
+/// let precondition = Precondition::is_subject_new("/user/456");
+/// assert_eq!(precondition.precondition_type(), "isSubjectNew");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RawPrecondition", into = "RawPrecondition")]
+pub enum Precondition {
+    /// The subject has no events yet (`isSubjectNew`)
+    IsSubjectNew {
+        subject: String,
+    },
+
+    /// The subject has at least one event (`isSubjectExisting`)
+    IsSubjectExisting {
+        subject: String,
+    },
+
+    /// The GDBQL query evaluates to a truthy result (`isQueryResultTrue`)
+    IsQueryResultTrue {
+        query: String,
+    },
+
+    /// A precondition type not known to this client
+    Other {
+        precondition_type: String,
+        payload: Value,
+    },
+}
+
+impl Precondition {
+    /// Require that the subject has no events yet
+    pub fn is_subject_new(subject: impl Into<String>) -> Self {
+        Precondition::IsSubjectNew {
+            subject: subject.into(),
+        }
+    }
+
+    /// Require that the subject has at least one event
+    pub fn is_subject_existing(subject: impl Into<String>) -> Self {
+        Precondition::IsSubjectExisting {
+            subject: subject.into(),
+        }
+    }
+
+    /// Require that the GDBQL query evaluates to a truthy result
+    pub fn is_query_result_true(query: impl Into<String>) -> Self {
+        Precondition::IsQueryResultTrue {
+            query: query.into(),
+        }
+    }
+
+    /// Create a precondition of a type not known to this client
+    pub fn other(precondition_type: impl Into<String>, payload: Value) -> Self {
+        Precondition::Other {
+            precondition_type: precondition_type.into(),
+            payload,
+        }
+    }
+
+    /// Precondition type as sent to the server
+    pub fn precondition_type(&self) -> &str {
+        match self {
+            Precondition::IsSubjectNew { .. } => "isSubjectNew",
+            Precondition::IsSubjectExisting { .. } => "isSubjectExisting",
+            Precondition::IsQueryResultTrue { .. } => "isQueryResultTrue",
+            Precondition::Other {
+                precondition_type, ..
+            } => precondition_type,
+        }
+    }
+
+    /// Precondition payload as sent to the server
+    pub fn payload(&self) -> Value {
+        match self {
+            Precondition::IsSubjectNew { subject } | Precondition::IsSubjectExisting { subject } => {
+                json!({ "subject": subject })
+            }
+            Precondition::IsQueryResultTrue { query } => json!({ "query": query }),
+            Precondition::Other { payload, .. } => payload.clone(),
+        }
+    }
+}
+
+/// Wire representation of a precondition
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Precondition {
-    /// Precondition type
+struct RawPrecondition {
     #[serde(rename = "type")]
-    pub precondition_type: String,
+    precondition_type: String,
+    #[serde(default)]
+    payload: Value,
+}
+
+impl From<RawPrecondition> for Precondition {
+    fn from(raw: RawPrecondition) -> Self {
+        let field = |name: &str| raw.payload.get(name).and_then(Value::as_str).map(str::to_string);
+
+        let typed = match raw.precondition_type.as_str() {
+            "isSubjectNew" => field("subject").map(Precondition::is_subject_new),
+            "isSubjectExisting" => field("subject").map(Precondition::is_subject_existing),
+            "isQueryResultTrue" => field("query").map(Precondition::is_query_result_true),
+            _ => None,
+        };
+
+        // Only use the typed variant if it serializes back to the same payload
+        match typed {
+            Some(typed) if typed.payload() == raw.payload => typed,
+            _ => Precondition::Other {
+                precondition_type: raw.precondition_type,
+                payload: raw.payload,
+            },
+        }
+    }
+}
 
-    /// Precondition payload
-    pub payload: Value,
+impl From<Precondition> for RawPrecondition {
+    fn from(precondition: Precondition) -> Self {
+        RawPrecondition {
+            precondition_type: precondition.precondition_type().to_string(),
+            payload: precondition.payload(),
+        }
+    }
 }
 
 /// Options for streaming events
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct StreamOptions {
     /// Lower bound event ID
     #[serde(rename = "lowerBound", skip_serializing_if = "Option::is_none")]
@@ -115,6 +550,8 @@ pub(crate) struct CommitRequest {
 /// Internal representation of an event for committing
 #[derive(Debug, Serialize)]
 pub(crate) struct CommitEventInternal {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
     pub source: String,
     pub subject: String,
     #[serde(rename = "type")]
@@ -122,6 +559,8 @@ pub(crate) struct CommitEventInternal {
     pub data: Value,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<CommitEventOptions>,
+    #[serde(flatten, serialize_with = "serialize_extensions")]
+    pub extensions: BTreeMap<String, Value>,
 }
 
 /// Request body for erasing data
@@ -135,3 +574,167 @@ pub(crate) struct EraseRequest {
 pub(crate) struct QueryRequest {
     pub query: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_time() {
+        let expected = Utc.with_ymd_and_hms(2024, 1, 1, 12, 30, 0).unwrap();
+        for value in [
+            "2024-01-01T12:30:00Z",
+            "2024-01-01T14:30:00+02:00",
+            "2024-01-01 12:30:00Z",
+            "2024-01-01t12:30:00z",
+            "2024-01-01T14:30:00+0200",
+            "2024-01-01T12:30:00",
+            "2024-01-01 12:30:00.000",
+        ] {
+            assert_eq!(parse_time(value), Some(expected), "{}", value);
+        }
+        assert_eq!(parse_time("yesterday"), None);
+    }
+
+    #[test]
+    fn test_cloud_event_extensions_round_trip() {
+        let json = json!({
+            "id": "1",
+            "source": "test",
+            "type": "t",
+            "subject": "/",
+            "specversion": "1.0",
+            "dataschema": "https://example.com/schema.json",
+            "traceparent": "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "sequence": 7,
+        });
+        let event: CloudEvent = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(event.dataschema.as_deref(), Some("https://example.com/schema.json"));
+        assert_eq!(
+            event.extensions.keys().collect::<Vec<_>>(),
+            ["sequence", "traceparent"]
+        );
+        assert_eq!(serde_json::to_value(&event).unwrap(), json);
+
+        let commit = CommitEvent::default().with_extension("correlationid", "c0ffee").unwrap();
+        let wire = serde_json::to_value(&commit).unwrap();
+        assert_eq!(wire["correlationid"], "c0ffee");
+        assert_eq!(serde_json::from_value::<CommitEvent>(wire).unwrap(), commit);
+    }
+
+    #[test]
+    fn test_commit_event_rejects_invalid_extension_names() {
+        for (name, violation) in [
+            ("subject", "extension name \"subject\" is reserved"),
+            ("data", "extension name \"data\" is reserved"),
+            ("correlationId", "extension name \"correlationId\" is not lower-case alphanumeric"),
+        ] {
+            match CommitEvent::default().with_extension(name, "x") {
+                Err(Error::InvalidEvent { violations, .. }) => assert_eq!(violations, [violation]),
+                other => panic!("unexpected result: {:?}", other),
+            }
+
+            let mut commit = CommitEvent::default();
+            commit.extensions.insert(name.to_string(), json!("x"));
+            let error = serde_json::to_value(&commit).unwrap_err();
+            assert_eq!(error.to_string(), violation);
+        }
+    }
+
+    #[test]
+    fn test_cloud_event_time_round_trip() {
+        let json = json!({
+            "id": "1",
+            "source": "test",
+            "type": "t",
+            "subject": "/",
+            "time": "2024-01-01T12:30:00.000000000+00:00",
+        });
+        let mut event: CloudEvent = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(event.time, Some(Utc.with_ymd_and_hms(2024, 1, 1, 12, 30, 0).unwrap()));
+        assert_eq!(event.specversion, "1.0");
+
+        let mut expected = json.clone();
+        expected["specversion"] = json!("1.0");
+        assert_eq!(serde_json::to_value(&event).unwrap(), expected);
+
+        event.time = Some(Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap());
+        assert_eq!(serde_json::to_value(&event).unwrap()["time"], "2024-01-02T00:00:00Z");
+
+        let mut unparsed = json;
+        unparsed["time"] = json!("soon");
+        let event: CloudEvent = serde_json::from_value(unparsed).unwrap();
+        assert_eq!((event.time, event.raw_time.as_deref()), (None, Some("soon")));
+        assert_eq!(serde_json::to_value(&event).unwrap()["time"], "soon");
+    }
+
+    #[test]
+    fn test_commit_result_from_response() {
+        let client_id = Uuid::new_v4();
+        let ids = [Some(client_id), None];
+
+        let body = r#"[{"id":"a","time":"2024-01-01T00:00:00Z"},{"id":"b"}]"#;
+        let result = CommitResult::from_response(body, &ids);
+        assert_eq!(result.events[0].id.as_deref(), Some("a"));
+        assert_eq!(result.events[0].time, Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()));
+        assert_eq!(result.events[0].raw_time.as_deref(), Some("2024-01-01T00:00:00Z"));
+        assert_eq!(result.last_event_id(), Some("b"));
+
+        let persisted = serde_json::to_value(&result.events[0]).unwrap();
+        assert_eq!(persisted, json!({ "id": "a", "time": "2024-01-01T00:00:00Z" }));
+        assert_eq!(serde_json::from_value::<CommittedEvent>(persisted).unwrap(), result.events[0]);
+
+        let body = "{\"id\":\"a\"}\n{\"id\":\"b\"}\n";
+        assert_eq!(CommitResult::from_response(body, &ids).last_event_id(), Some("b"));
+
+        let body = r#"{"events":[{"id":"a"},{"id":"b"}]}"#;
+        assert_eq!(CommitResult::from_response(body, &ids).last_event_id(), Some("b"));
+
+        for body in ["", "OK", r#"[{"id":"a"}]"#] {
+            let result = CommitResult::from_response(body, &ids);
+            assert_eq!(result.events.len(), 2);
+            assert_eq!(result.events[0].id, Some(client_id.to_string()));
+            assert_eq!(result.events[1], CommittedEvent::default());
+        }
+    }
+
+    #[test]
+    fn test_precondition_wire_format() {
+        assert_eq!(
+            serde_json::to_value(Precondition::is_subject_new("/user/1")).unwrap(),
+            json!({ "type": "isSubjectNew", "payload": { "subject": "/user/1" } })
+        );
+        assert_eq!(
+            serde_json::to_value(Precondition::is_subject_existing("/user/1")).unwrap(),
+            json!({ "type": "isSubjectExisting", "payload": { "subject": "/user/1" } })
+        );
+        assert_eq!(
+            serde_json::to_value(Precondition::is_query_result_true("FROM e IN events")).unwrap(),
+            json!({ "type": "isQueryResultTrue", "payload": { "query": "FROM e IN events" } })
+        );
+    }
+
+    #[test]
+    fn test_precondition_round_trip() {
+        let preconditions = vec![
+            Precondition::is_subject_new("/a"),
+            Precondition::is_subject_existing("/b"),
+            Precondition::is_query_result_true("STREAM e FROM events MAP COUNT() == 0"),
+            Precondition::other("isSomethingElse", json!({ "value": 1 })),
+        ];
+
+        let json = serde_json::to_string(&preconditions).unwrap();
+        let parsed: Vec<Precondition> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, preconditions);
+    }
+
+    #[test]
+    fn test_precondition_unexpected_payload_is_kept() {
+        let raw = json!({ "type": "isSubjectNew", "payload": { "subject": "/a", "extra": true } });
+        let parsed: Precondition = serde_json::from_value(raw.clone()).unwrap();
+
+        assert!(matches!(parsed, Precondition::Other { .. }));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), raw);
+    }
+}
@@ -0,0 +1,267 @@
+//! Validation of CloudEvents attributes against the CloudEvents 1.0 spec
+
+use crate::error::{Error, Result};
+use crate::types::CloudEvent;
+
+/// Spec versions this client understands
+const SPEC_VERSIONS: [&str; 1] = ["1.0"];
+
+/// Attributes an extension must not shadow, the CloudEvents context attributes, `data` and `options`
+const RESERVED_NAMES: [&str; 10] = [
+    "id", "source", "specversion", "type", "datacontenttype", "dataschema", "subject", "time", "data", "options",
+];
+
+impl CloudEvent {
+    /// Check the attributes against the CloudEvents 1.0 rules
+    ///
+    /// `id`, `source` and `type` must not be empty, `source` must be a URI
+    /// reference, `specversion` must be `1.0`, `datacontenttype` must be a
+    /// media type, `dataschema` an absolute URI, `subject` must not be empty,
+    /// `time`, if present, must be a valid timestamp, and extension names may
+    /// only contain lower-case letters and digits and must not be the name
+    /// of another attribute. All violations are reported at once in
+    /// [`Error::InvalidEvent`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use genesisdb_io_client::{CloudEvent, Error};
+    /// let event: CloudEvent = serde_json::from_value(serde_json::json!({
+    ///     "id": "1",
+    ///     "source": "not a uri",
+    ///     "type": "io.genesisdb.app.user-created",
+    ///     "subject": "/user/1",
+    /// }))?;
+    ///
+    /// match event.validate() {
+    ///     Err(Error::InvalidEvent { violations, .. }) => {
+    ///         assert_eq!(violations, ["source is not a URI reference"]);
+    ///     }
+    ///     other => panic!("unexpected result: {:?}", other),
+    /// }
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn validate(&self) -> Result<()> {
+        let mut violations = Vec::new();
+
+        if self.id.is_empty() {
+            violations.push("id is empty".to_string());
+        }
+        if self.source.is_empty() {
+            violations.push("source is empty".to_string());
+        } else if !is_uri_reference(&self.source) {
+            violations.push("source is not a URI reference".to_string());
+        }
+        if self.event_type.is_empty() {
+            violations.push("type is empty".to_string());
+        }
+        if !SPEC_VERSIONS.contains(&self.specversion.as_str()) {
+            violations.push(format!("unsupported specversion {:?}", self.specversion));
+        }
+        if self.subject.is_empty() {
+            violations.push("subject is empty".to_string());
+        }
+        if let (None, Some(raw)) = (&self.time, &self.raw_time) {
+            violations.push(format!("time {:?} is not a timestamp", raw));
+        }
+        if let Some(content_type) = &self.datacontenttype {
+            if !is_media_type(content_type) {
+                violations.push(format!(
+                    "datacontenttype {:?} is not a media type",
+                    content_type
+                ));
+            }
+        }
+
+        if let Some(schema) = &self.dataschema {
+            if !is_absolute_uri(schema) {
+                violations.push(format!("dataschema {:?} is not an absolute URI", schema));
+            }
+        }
+        violations.extend(self.extensions.keys().filter_map(|name| extension_name_violation(name)));
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidEvent {
+                event_id: self.id.clone(),
+                violations,
+            })
+        }
+    }
+}
+
+/// Whether the value is a URI reference as defined by RFC 3986
+///
+/// Checks the character set, percent-encoding and, for absolute URIs, the
+/// scheme. The components are not validated further.
+fn is_uri_reference(value: &str) -> bool {
+    const ALLOWED: &str = "-._~:/?#[]@!$&'()*+,;=";
+
+    let bytes = value.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let escaped = bytes.get(i + 1..i + 3);
+                if !escaped.is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+                    return false;
+                }
+                i += 3;
+                continue;
+            }
+            c if c.is_ascii_alphanumeric() || ALLOWED.as_bytes().contains(&c) => {}
+            _ => return false,
+        }
+        i += 1;
+    }
+
+    // A colon before any `/`, `?` or `#` ends the scheme of an absolute URI
+    let head = value.split(['/', '?', '#']).next().unwrap_or_default();
+    match head.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        }
+        None => true,
+    }
+}
+
+/// Whether the value is a URI reference with a scheme
+fn is_absolute_uri(value: &str) -> bool {
+    let head = value.split(['/', '?', '#']).next().unwrap_or_default();
+    head.contains(':') && is_uri_reference(value)
+}
+
+/// Why the name cannot be used for an extension attribute, if it cannot
+///
+/// Names must be lower-case alphanumeric and must not be the name of
+/// another attribute of the event.
+pub(crate) fn extension_name_violation(name: &str) -> Option<String> {
+    if !is_attribute_name(name) {
+        Some(format!("extension name {:?} is not lower-case alphanumeric", name))
+    } else if RESERVED_NAMES.contains(&name) {
+        Some(format!("extension name {:?} is reserved", name))
+    } else {
+        None
+    }
+}
+
+/// Whether the value is a valid attribute name, lower-case ASCII letters and digits
+fn is_attribute_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+/// Whether the value is a media type as defined by RFC 2045, e.g. `application/json; charset=utf-8`
+fn is_media_type(value: &str) -> bool {
+    let mut parts = value.split(';');
+    let essence = parts.next().unwrap_or_default().trim();
+    let valid_essence = essence
+        .split_once('/')
+        .is_some_and(|(kind, subtype)| is_token(kind) && is_token(subtype));
+
+    valid_essence
+        && parts.all(|parameter| {
+            parameter.split_once('=').is_some_and(|(name, value)| {
+                let value = value.trim();
+                let quoted = value.len() >= 2 && value.starts_with('"') && value.ends_with('"');
+                is_token(name.trim()) && (quoted || is_token(value))
+            })
+        })
+}
+
+/// Whether the value is an RFC 2045 token
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !"()<>@,;:\\\"/[]?=".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(fields: serde_json::Value) -> CloudEvent {
+        let mut event = json!({
+            "id": "1",
+            "source": "io.genesisdb.app",
+            "type": "io.genesisdb.app.user-created",
+            "subject": "/user/1",
+            "time": "2024-01-01T00:00:00Z",
+            "datacontenttype": "application/json",
+        });
+        event.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(event).unwrap()
+    }
+
+    fn violations(event: &CloudEvent) -> Vec<String> {
+        match event.validate() {
+            Ok(()) => Vec::new(),
+            Err(Error::InvalidEvent { violations, .. }) => violations,
+            Err(other) => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_valid_events() {
+        for fields in [
+            json!({}),
+            json!({ "source": "https://example.com/users?id=1#x" }),
+            json!({ "source": "urn:uuid:6e8bc430-9c3a-11d9-9669-0800200c9a66" }),
+            json!({ "source": "/relative/path%20with%20spaces" }),
+            json!({ "datacontenttype": "text/plain; charset=\"utf-8\"" }),
+            json!({ "specversion": "1.0", "time": "2024-01-01 00:00:00" }),
+            json!({ "dataschema": "https://example.com/schemas/user.json", "correlationid": "c0ffee" }),
+        ] {
+            assert_eq!(violations(&event(fields.clone())), Vec::<String>::new(), "{}", fields);
+        }
+    }
+
+    #[test]
+    fn test_invalid_events() {
+        let invalid = event(json!({
+            "id": "",
+            "source": "has space",
+            "type": "",
+            "specversion": "0.3",
+            "subject": "",
+            "time": "yesterday",
+            "datacontenttype": "json",
+            "dataschema": "schemas/user.json",
+            "traceParent": "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        }));
+        assert_eq!(
+            violations(&invalid),
+            [
+                "id is empty",
+                "source is not a URI reference",
+                "type is empty",
+                "unsupported specversion \"0.3\"",
+                "subject is empty",
+                "time \"yesterday\" is not a timestamp",
+                "datacontenttype \"json\" is not a media type",
+                "dataschema \"schemas/user.json\" is not an absolute URI",
+                "extension name \"traceParent\" is not lower-case alphanumeric",
+            ]
+        );
+
+        for source in ["", "1http://example.com", "bad%2", "ümlaut"] {
+            assert_eq!(violations(&event(json!({ "source": source }))).len(), 1, "{}", source);
+        }
+        for content_type in ["application/", "application/json; charset", "text/plain; a=b c"] {
+            assert_eq!(
+                violations(&event(json!({ "datacontenttype": content_type }))).len(),
+                1,
+                "{}",
+                content_type
+            );
+        }
+    }
+}
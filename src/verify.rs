@@ -0,0 +1,502 @@
+//! Client-side verification of event integrity
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::types::{parse_time, StreamOptions};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Attributes every event must carry, the CloudEvents ones plus `subject`
+const REQUIRED_ATTRIBUTES: [&str; 5] = ["id", "source", "specversion", "type", "subject"];
+
+/// Options for verifying events
+///
+/// Hash fields are extension attributes of the events. Events without them
+/// are not checked for hashes. The hashes themselves are not recomputed,
+/// only their uniqueness and how events link to each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Attribute holding the hash of an event
+    pub hash_field: String,
+    /// Attribute holding the hash of the event before it in the chain
+    pub previous_hash_field: String,
+    /// Whether every event must link to the one streamed right before it
+    ///
+    /// Only holds when the stream covers the whole chain, e.g. all events
+    /// of `/` without lower bound. Otherwise an event may only link to an
+    /// event that was streamed before it, or to one outside the stream.
+    pub contiguous_chain: bool,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            hash_field: "hash".to_string(),
+            previous_hash_field: "predecessorhash".to_string(),
+            contiguous_chain: false,
+        }
+    }
+}
+
+/// An inconsistency found in the events
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Anomaly {
+    /// Position of the event in the stream, starting at 0
+    pub position: usize,
+    /// Id of the event, if it has one
+    pub event_id: Option<String>,
+    #[serde(flatten)]
+    pub kind: AnomalyKind,
+}
+
+/// The kind of an [`Anomaly`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// The event is not a JSON object
+    Malformed,
+    /// A required attribute is missing or empty
+    MissingAttribute { attribute: String },
+    /// The id was already used by an earlier event
+    DuplicateId { first_position: usize },
+    /// The time is missing or not a timestamp
+    ///
+    /// Times are parsed as leniently as [`CloudEvent::time`](crate::CloudEvent::time).
+    InvalidTime { value: Option<String> },
+    /// The time is before the time of the previous event
+    TimeNotMonotonic {
+        previous: DateTime<Utc>,
+        time: DateTime<Utc>,
+    },
+    /// The hash was already used by an earlier event
+    DuplicateHash { first_position: usize },
+    /// The event does not link to the hash it should
+    BrokenChain {
+        expected: Option<String>,
+        found: Option<String>,
+    },
+}
+
+impl fmt::Display for AnomalyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnomalyKind::Malformed => write!(f, "not a JSON object"),
+            AnomalyKind::MissingAttribute { attribute } => {
+                write!(f, "missing attribute `{}`", attribute)
+            }
+            AnomalyKind::DuplicateId { first_position } => {
+                write!(f, "id already used by event {}", first_position)
+            }
+            AnomalyKind::InvalidTime { value: None } => write!(f, "missing time"),
+            AnomalyKind::InvalidTime { value: Some(value) } => {
+                write!(f, "invalid time {:?}", value)
+            }
+            AnomalyKind::TimeNotMonotonic { previous, time } => write!(
+                f,
+                "time {} is before the previous event's time {}",
+                time.to_rfc3339(),
+                previous.to_rfc3339()
+            ),
+            AnomalyKind::DuplicateHash { first_position } => {
+                write!(f, "hash already used by event {}", first_position)
+            }
+            AnomalyKind::BrokenChain { expected, found } => write!(
+                f,
+                "links to hash {}, expected {}",
+                found.as_deref().unwrap_or("none"),
+                expected.as_deref().unwrap_or("none")
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "event {}", self.position)?;
+        if let Some(id) = &self.event_id {
+            write!(f, " ({})", id)?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// Result of verifying a sequence of events
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IntegrityReport {
+    /// Number of events checked
+    pub events_checked: usize,
+    /// Id of the first event checked
+    pub first_event_id: Option<String>,
+    /// Id of the last event checked
+    pub last_event_id: Option<String>,
+    /// Inconsistencies found, in stream order
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl IntegrityReport {
+    /// Whether no anomalies were found
+    pub fn is_ok(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} events checked, {} anomalies",
+            self.events_checked,
+            self.anomalies.len()
+        )?;
+        for anomaly in &self.anomalies {
+            write!(f, "\n  {}", anomaly)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks events one by one, in the order they were stored
+///
+/// Use [`Client::verify_events`] to check a subject on the server, or feed
+/// events to a verifier directly, e.g. in tests.
+///
+/// # Example
+///
+/// ```
+/// # use genesisdb_io_client::{IntegrityVerifier, VerifyOptions};
+/// # use serde_json::json;
+/// let mut verifier = IntegrityVerifier::new(VerifyOptions::default());
+/// verifier.check(&json!({
+///     "id": "1", "source": "io.genesisdb.app", "specversion": "1.0",
+///     "type": "created", "subject": "/user/1", "time": "2026-10-16T08:00:00Z"
+/// }));
+/// verifier.check(&json!({
+///     "id": "1", "source": "io.genesisdb.app", "specversion": "1.0",
+///     "type": "updated", "subject": "/user/1", "time": "2026-10-16T07:00:00Z"
+/// }));
+///
+/// let report = verifier.finish();
+/// assert_eq!(report.anomalies.len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct IntegrityVerifier {
+    options: VerifyOptions,
+    report: IntegrityReport,
+    ids: HashMap<String, usize>,
+    hashes: HashMap<String, usize>,
+    previous_time: Option<DateTime<Utc>>,
+    previous_hash: Option<String>,
+    /// Links to hashes not seen yet, checked once the stream has ended
+    forward_links: Vec<(usize, Option<String>, String)>,
+}
+
+impl IntegrityVerifier {
+    pub fn new(options: VerifyOptions) -> Self {
+        Self {
+            options,
+            report: IntegrityReport::default(),
+            ids: HashMap::new(),
+            hashes: HashMap::new(),
+            previous_time: None,
+            previous_hash: None,
+            forward_links: Vec::new(),
+        }
+    }
+
+    /// Check the next event
+    pub fn check(&mut self, event: &Value) {
+        let position = self.report.events_checked;
+        self.report.events_checked += 1;
+
+        let Some(fields) = event.as_object() else {
+            self.report.anomalies.push(Anomaly {
+                position,
+                event_id: None,
+                kind: AnomalyKind::Malformed,
+            });
+            return;
+        };
+
+        let event_id = fields.get("id").and_then(Value::as_str).map(str::to_string);
+        if let Some(id) = &event_id {
+            self.report.first_event_id.get_or_insert_with(|| id.clone());
+            self.report.last_event_id = Some(id.clone());
+        }
+        let mut anomalies = Vec::new();
+
+        for attribute in REQUIRED_ATTRIBUTES {
+            if fields
+                .get(attribute)
+                .and_then(Value::as_str)
+                .is_none_or(str::is_empty)
+            {
+                anomalies.push(AnomalyKind::MissingAttribute {
+                    attribute: attribute.to_string(),
+                });
+            }
+        }
+
+        if let Some(id) = &event_id {
+            if let Some(&first_position) = self.ids.get(id) {
+                anomalies.push(AnomalyKind::DuplicateId { first_position });
+            } else {
+                self.ids.insert(id.clone(), position);
+            }
+        }
+
+        let time = fields.get("time");
+        match time
+            .and_then(Value::as_str)
+            .and_then(parse_time)
+        {
+            Some(time) => {
+                if let Some(previous) = self.previous_time.filter(|previous| time < *previous) {
+                    anomalies.push(AnomalyKind::TimeNotMonotonic { previous, time });
+                }
+                self.previous_time = Some(time);
+            }
+            None => anomalies.push(AnomalyKind::InvalidTime {
+                value: time.map(|time| match time {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                }),
+            }),
+        }
+
+        let hash = fields
+            .get(&self.options.hash_field)
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let previous_hash = fields
+            .get(&self.options.previous_hash_field)
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        if let Some(hash) = &hash {
+            if let Some(&first_position) = self.hashes.get(hash) {
+                anomalies.push(AnomalyKind::DuplicateHash { first_position });
+            } else {
+                self.hashes.insert(hash.clone(), position);
+            }
+        }
+
+        if self.options.contiguous_chain {
+            let linked = hash.is_some() || previous_hash.is_some();
+            if position > 0 && linked && previous_hash != self.previous_hash {
+                anomalies.push(AnomalyKind::BrokenChain {
+                    expected: self.previous_hash.clone(),
+                    found: previous_hash,
+                });
+            }
+        } else if let Some(link) = previous_hash {
+            if hash.as_ref() == Some(&link) {
+                anomalies.push(AnomalyKind::BrokenChain {
+                    expected: None,
+                    found: Some(link),
+                });
+            } else if !self.hashes.contains_key(&link) {
+                self.forward_links.push((position, event_id.clone(), link));
+            }
+        }
+        self.previous_hash = hash;
+
+        self.report
+            .anomalies
+            .extend(anomalies.into_iter().map(|kind| Anomaly {
+                position,
+                event_id: event_id.clone(),
+                kind,
+            }));
+    }
+
+    /// Finish the verification and return the report
+    pub fn finish(mut self) -> IntegrityReport {
+        // Links to events streamed later point forward in time
+        for (position, event_id, link) in self.forward_links {
+            if self.hashes.contains_key(&link) {
+                self.report.anomalies.push(Anomaly {
+                    position,
+                    event_id,
+                    kind: AnomalyKind::BrokenChain {
+                        expected: None,
+                        found: Some(link),
+                    },
+                });
+            }
+        }
+        self.report.anomalies.sort_by_key(|anomaly| anomaly.position);
+        self.report
+    }
+}
+
+impl Client {
+    /// Verify the integrity of the events of a subject and its nested subjects
+    ///
+    /// Streams the events and checks that ids are unique, times do not go
+    /// backwards, required attributes are present and hash fields, where the
+    /// server provides them, form a consistent chain. Use the lower bound of
+    /// `options` to verify a range of events.
+    ///
+    /// Anomalies are reported, not returned as errors; an error means the
+    /// events could not be streamed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use genesisdb_io_client::{Client, VerifyOptions};
+    /// # async fn example(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// let report = client
+    ///     .verify_events("/tenant/42", None, &VerifyOptions::default())
+    ///     .await?;
+    /// assert!(report.is_ok(), "{}", report);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn verify_events(
+        &self,
+        subject: &str,
+        options: Option<StreamOptions>,
+        verify: &VerifyOptions,
+    ) -> Result<IntegrityReport> {
+        let mut events = self.stream_events_as::<Value>(subject, options).await?;
+        let mut verifier = IntegrityVerifier::new(verify.clone());
+
+        while let Some(event) = events.next().await {
+            verifier.check(&event?);
+        }
+
+        Ok(verifier.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(id: &str, time: &str, hash: &str, previous: Option<&str>) -> Value {
+        let mut event = json!({
+            "id": id,
+            "source": "test",
+            "specversion": "1.0",
+            "type": "test",
+            "subject": "/test",
+            "time": time,
+            "hash": hash,
+        });
+        if let Some(previous) = previous {
+            event["predecessorhash"] = json!(previous);
+        }
+        event
+    }
+
+    fn kinds(report: &IntegrityReport) -> Vec<(usize, &AnomalyKind)> {
+        report
+            .anomalies
+            .iter()
+            .map(|anomaly| (anomaly.position, &anomaly.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_consistent_events() {
+        let mut verifier = IntegrityVerifier::new(VerifyOptions {
+            contiguous_chain: true,
+            ..Default::default()
+        });
+        verifier.check(&event("1", "2026-10-16T08:00:00Z", "a", None));
+        verifier.check(&event("2", "2026-10-16T08:00:00Z", "b", Some("a")));
+        verifier.check(&event("3", "2026-10-16T10:00:01+02:00", "c", Some("b")));
+        // Accepted like CloudEvent::time, without offset as UTC
+        verifier.check(&event("4", "2026-10-16 08:00:02", "d", Some("c")));
+
+        let report = verifier.finish();
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!(report.events_checked, 4);
+        assert_eq!(report.first_event_id.as_deref(), Some("1"));
+        assert_eq!(report.last_event_id.as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn test_attribute_anomalies() {
+        let mut verifier = IntegrityVerifier::new(VerifyOptions::default());
+        verifier.check(&event("1", "2026-10-16T08:00:00Z", "a", None));
+        verifier.check(&json!({
+            "id": "1",
+            "source": "",
+            "specversion": "1.0",
+            "type": "t",
+            "subject": "/",
+            "time": "yesterday",
+        }));
+        verifier.check(&event("2", "2026-10-16T07:59:59Z", "b", None));
+        verifier.check(&json!("not an event"));
+
+        let report = verifier.finish();
+        assert_eq!(
+            kinds(&report),
+            vec![
+                (1, &AnomalyKind::MissingAttribute { attribute: "source".to_string() }),
+                (1, &AnomalyKind::DuplicateId { first_position: 0 }),
+                (1, &AnomalyKind::InvalidTime { value: Some("yesterday".to_string()) }),
+                (
+                    2,
+                    &AnomalyKind::TimeNotMonotonic {
+                        previous: "2026-10-16T08:00:00Z".parse().unwrap(),
+                        time: "2026-10-16T07:59:59Z".parse().unwrap(),
+                    }
+                ),
+                (3, &AnomalyKind::Malformed),
+            ]
+        );
+        assert_eq!(
+            report.anomalies[1].to_string(),
+            "event 1 (1): id already used by event 0"
+        );
+    }
+
+    #[test]
+    fn test_chain_anomalies() {
+        let mut verifier = IntegrityVerifier::new(VerifyOptions::default());
+        verifier.check(&event("1", "2026-10-16T08:00:00Z", "a", Some("outside")));
+        verifier.check(&event("2", "2026-10-16T08:00:01Z", "b", Some("c")));
+        verifier.check(&event("3", "2026-10-16T08:00:02Z", "c", Some("a")));
+        verifier.check(&event("4", "2026-10-16T08:00:03Z", "a", Some("c")));
+
+        let report = verifier.finish();
+        assert_eq!(
+            kinds(&report),
+            vec![
+                (
+                    1,
+                    &AnomalyKind::BrokenChain {
+                        expected: None,
+                        found: Some("c".to_string())
+                    }
+                ),
+                (3, &AnomalyKind::DuplicateHash { first_position: 0 }),
+            ]
+        );
+
+        let mut verifier = IntegrityVerifier::new(VerifyOptions {
+            contiguous_chain: true,
+            ..Default::default()
+        });
+        verifier.check(&event("1", "2026-10-16T08:00:00Z", "a", None));
+        verifier.check(&event("2", "2026-10-16T08:00:01Z", "b", Some("x")));
+        assert_eq!(
+            kinds(&verifier.finish()),
+            vec![(
+                1,
+                &AnomalyKind::BrokenChain {
+                    expected: Some("a".to_string()),
+                    found: Some("x".to_string())
+                }
+            )]
+        );
+    }
+}
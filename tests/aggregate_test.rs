@@ -0,0 +1,223 @@
+//! Tests for aggregates and repositories
+//!
+//! Run with: cargo test --features testing,derive --test aggregate_test
+
+#![cfg(all(feature = "testing", feature = "derive"))]
+
+use genesisdb_io_client::testing::FakeServer;
+use genesisdb_io_client::{
+    Aggregate, Client, ClientConfig, CommandError, CommitEvent, DomainEvent, Error, EventSet, Repository,
+};
+use mockito::Server;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Serialize, Deserialize, DomainEvent)]
+#[domain_event(type = "io.genesisdb.app.order-placed", source = "io.genesisdb.app")]
+struct OrderPlaced {}
+
+#[derive(Debug, Serialize, Deserialize, DomainEvent)]
+#[domain_event(type = "io.genesisdb.app.item-added", source = "io.genesisdb.app")]
+struct ItemAdded {
+    sku: String,
+}
+
+#[derive(Debug, EventSet)]
+enum OrderEvent {
+    Placed(OrderPlaced),
+    ItemAdded(ItemAdded),
+}
+
+enum OrderCommand {
+    Place,
+    PlaceWith(Vec<String>),
+    AddItem(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum OrderError {
+    AlreadyPlaced,
+    NotPlaced,
+}
+
+#[derive(Default)]
+struct Order {
+    placed: bool,
+    items: Vec<String>,
+}
+
+impl Aggregate for Order {
+    type Event = OrderEvent;
+    type Command = OrderCommand;
+    type Error = OrderError;
+
+    fn subject(id: &str) -> String {
+        format!("/order/{}", id)
+    }
+
+    fn apply(&mut self, event: &OrderEvent) {
+        match event {
+            OrderEvent::Placed(_) => self.placed = true,
+            OrderEvent::ItemAdded(item) => self.items.push(item.sku.clone()),
+        }
+    }
+
+    fn handle(&self, command: &OrderCommand) -> Result<Vec<OrderEvent>, OrderError> {
+        match command {
+            OrderCommand::Place if self.placed => Err(OrderError::AlreadyPlaced),
+            OrderCommand::Place => Ok(vec![OrderEvent::Placed(OrderPlaced {})]),
+            OrderCommand::PlaceWith(_) if self.placed => Err(OrderError::AlreadyPlaced),
+            OrderCommand::PlaceWith(skus) => {
+                let items = skus.iter().map(|sku| OrderEvent::ItemAdded(ItemAdded { sku: sku.clone() }));
+                Ok(std::iter::once(OrderEvent::Placed(OrderPlaced {})).chain(items).collect())
+            }
+            OrderCommand::AddItem(_) if !self.placed => Err(OrderError::NotPlaced),
+            OrderCommand::AddItem(sku) => Ok(vec![OrderEvent::ItemAdded(ItemAdded { sku: sku.clone() })]),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_execute_commands() {
+    let server = FakeServer::start().await.unwrap();
+    let repository = Repository::<Order>::new(server.client());
+
+    assert!(matches!(
+        repository.execute("1", &OrderCommand::AddItem("a".to_string())).await,
+        Err(CommandError::Rejected(OrderError::NotPlaced))
+    ));
+
+    repository.execute("1", &OrderCommand::Place).await.unwrap();
+    repository.execute("1", &OrderCommand::AddItem("a".to_string())).await.unwrap();
+    repository.execute("1", &OrderCommand::AddItem("b".to_string())).await.unwrap();
+
+    assert!(matches!(
+        repository.execute("1", &OrderCommand::Place).await,
+        Err(CommandError::Rejected(OrderError::AlreadyPlaced))
+    ));
+
+    let order = repository.load("1").await.unwrap();
+    assert!(order.state.placed);
+    assert_eq!(order.state.items, vec!["a", "b"]);
+    assert_eq!(server.events().len(), 3);
+}
+
+#[tokio::test]
+async fn test_execute_after_command_with_several_events() {
+    let server = FakeServer::start().await.unwrap();
+    let repository = Repository::<Order>::new(server.client()).max_conflict_retries(0);
+
+    let skus = vec!["a".to_string(), "b".to_string()];
+    repository.execute("1", &OrderCommand::PlaceWith(skus)).await.unwrap();
+    repository.execute("1", &OrderCommand::AddItem("c".to_string())).await.unwrap();
+
+    let order = repository.load("1").await.unwrap();
+    assert_eq!(order.state.items, vec!["a", "b", "c"]);
+    assert_eq!(order.version, 4);
+}
+
+#[tokio::test]
+async fn test_child_subject_writes_conflict() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+    let repository = Repository::<Order>::new(client.clone());
+
+    repository.execute("1", &OrderCommand::Place).await.unwrap();
+    let loaded = repository.load("1").await.unwrap();
+
+    // A write to a child subject changes the state the command was handled against
+    client
+        .commit_events(
+            vec![CommitEvent {
+                source: "io.genesisdb.app".to_string(),
+                subject: "/order/1/line/2".to_string(),
+                event_type: "io.genesisdb.app.item-added".to_string(),
+                data: json!({ "sku": "a" }),
+                ..Default::default()
+            }],
+            None,
+        )
+        .await
+        .unwrap();
+
+    let item = OrderEvent::ItemAdded(ItemAdded { sku: "b".to_string() });
+    let result = repository.commit(&loaded, &[item]).await;
+    assert!(matches!(result, Err(Error::PreconditionFailed { .. })));
+
+    let reloaded = repository.load("1").await.unwrap();
+    assert_eq!(reloaded.state.items, vec!["a"]);
+    assert_eq!(reloaded.version, 2);
+    let item = OrderEvent::ItemAdded(ItemAdded { sku: "b".to_string() });
+    repository.commit(&reloaded, &[item]).await.unwrap();
+    assert_eq!(repository.load("1").await.unwrap().state.items, vec!["a", "b"]);
+}
+
+#[tokio::test]
+async fn test_commit_detects_concurrent_writes() {
+    let server = FakeServer::start().await.unwrap();
+    let repository = Repository::<Order>::new(server.client());
+
+    let fresh = repository.load("1").await.unwrap();
+    repository.execute("1", &OrderCommand::Place).await.unwrap();
+    let result = repository.commit(&fresh, &[OrderEvent::Placed(OrderPlaced {})]).await;
+    assert!(matches!(result, Err(Error::PreconditionFailed { .. })));
+
+    let stale = repository.load("1").await.unwrap();
+    repository.execute("1", &OrderCommand::AddItem("a".to_string())).await.unwrap();
+    let item = OrderEvent::ItemAdded(ItemAdded { sku: "b".to_string() });
+    let result = repository.commit(&stale, &[item]).await;
+    assert!(matches!(result, Err(Error::PreconditionFailed { .. })));
+
+    let current = repository.load("1").await.unwrap();
+    let item = OrderEvent::ItemAdded(ItemAdded { sku: "b".to_string() });
+    repository.commit(&current, &[item]).await.unwrap();
+    assert_eq!(server.events().len(), 3);
+}
+
+#[tokio::test]
+async fn test_execute_retries_on_conflict() {
+    let mut server = Server::new_async().await;
+    let placed = json!({
+        "id": "1",
+        "source": "io.genesisdb.app",
+        "subject": "/order/1",
+        "type": "io.genesisdb.app.order-placed",
+        "data": {}
+    });
+    let stream = server
+        .mock("POST", "/api/v1/stream")
+        .with_status(200)
+        .with_body(placed.to_string())
+        .expect(2)
+        .create_async()
+        .await;
+    let conflict = server
+        .mock("POST", "/api/v1/commit")
+        .with_status(412)
+        .expect(1)
+        .create_async()
+        .await;
+    let commit = server
+        .mock("POST", "/api/v1/commit")
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = Client::new(ClientConfig {
+        api_url: server.url(),
+        api_version: "v1".to_string(),
+        auth_token: "test-token".to_string(),
+    })
+    .unwrap();
+    let repository = Repository::<Order>::new(client);
+    let events = repository
+        .execute("1", &OrderCommand::AddItem("a".to_string()))
+        .await
+        .unwrap();
+
+    stream.assert_async().await;
+    conflict.assert_async().await;
+    commit.assert_async().await;
+    assert_eq!(events.len(), 1);
+}
@@ -0,0 +1,137 @@
+//! Tests for the `genesisdb` command-line tool against the in-memory fake server
+//!
+//! Run with: cargo test --features cli,testing --test cli_test
+
+#![cfg(all(feature = "cli", feature = "testing"))]
+
+use genesisdb_io_client::testing::FakeServer;
+use genesisdb_io_client::{ArchiveManifest, CommitEvent};
+use serde_json::Value;
+use std::process::Output;
+use tokio::process::Command;
+
+async fn genesisdb(server: &FakeServer, args: &[&str]) -> Output {
+    let config = server.config();
+    Command::new(env!("CARGO_BIN_EXE_genesisdb"))
+        .args(args)
+        .env_clear()
+        .env("GENESISDB_API_URL", config.api_url)
+        .env("GENESISDB_AUTH_TOKEN", config.auth_token)
+        .output()
+        .await
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[tokio::test]
+async fn test_commit_stream_and_query() {
+    let server = FakeServer::start().await.unwrap();
+
+    let pong: Value = serde_json::from_str(&stdout(&genesisdb(&server, &["ping"]).await)).unwrap();
+    assert_eq!(pong, "pong");
+    assert_eq!(stdout(&genesisdb(&server, &["ping", "-o", "table"]).await), "pong\n");
+
+    let file = std::env::temp_dir().join(format!("genesisdb-cli-{}.ndjson", uuid::Uuid::new_v4()));
+    std::fs::write(
+        &file,
+        concat!(
+            "{\"source\":\"io.genesisdb.cli\",\"subject\":\"/user/1\",\"type\":\"created\",\"data\":{\"name\":\"Bruce\"}}\n",
+            "\n",
+            "{\"source\":\"io.genesisdb.cli\",\"subject\":\"/user/2\",\"type\":\"created\",\"data\":{\"name\":\"Alfred\"}}\n",
+        ),
+    )
+    .unwrap();
+    let committed: Value =
+        serde_json::from_str(&stdout(&genesisdb(&server, &["commit", "--file", file.to_str().unwrap()]).await)).unwrap();
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(committed.as_array().map(Vec::len), Some(2));
+    assert_eq!(server.events().len(), 2);
+
+    let lines = stdout(&genesisdb(&server, &["stream", "/user", "--output", "ndjson"]).await);
+    let subjects: Vec<String> = lines
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap()["subject"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(subjects, ["/user/1", "/user/2"]);
+
+    let streamed: Value = serde_json::from_str(&stdout(&genesisdb(&server, &["stream", "/user"]).await)).unwrap();
+    assert_eq!(streamed, serde_json::to_value(server.events()).unwrap());
+    let empty: Value = serde_json::from_str(&stdout(&genesisdb(&server, &["stream", "/order"]).await)).unwrap();
+    assert_eq!(empty, serde_json::json!([]));
+
+    let table = stdout(
+        &genesisdb(
+            &server,
+            &["q", "FROM e IN events WHERE e.subject == '/user/2' PROJECT INTO { subject: e.subject }", "-o", "table"],
+        )
+        .await,
+    );
+    assert_eq!(table, "subject\n/user/2\n");
+
+    let report: Value = serde_json::from_str(&stdout(&genesisdb(&server, &["verify", "/user"]).await)).unwrap();
+    assert_eq!(report[0]["events_checked"], 2);
+}
+
+#[tokio::test]
+async fn test_errors_exit_with_failure() {
+    let server = FakeServer::start().await.unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_genesisdb"))
+        .args(["--url", &server.url(), "--token", "wrong-token", "ping"])
+        .env_clear()
+        .output()
+        .await
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("wrong-token"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_genesisdb"))
+        .arg("ping")
+        .env_clear()
+        .output()
+        .await
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("GENESISDB_API_URL"));
+}
+
+#[tokio::test]
+async fn test_export_and_import() {
+    let source = FakeServer::start().await.unwrap();
+    source
+        .client()
+        .commit_events(
+            vec![CommitEvent {
+                source: "io.genesisdb.cli".to_string(),
+                subject: "/tenant/42".to_string(),
+                event_type: "created".to_string(),
+                data: serde_json::json!({ "name": "Wayne Enterprises" }),
+                ..Default::default()
+            }],
+            None,
+        )
+        .await
+        .unwrap();
+
+    let file = std::env::temp_dir().join(format!("genesisdb-cli-{}.ndjson", uuid::Uuid::new_v4()));
+    let path = file.to_str().unwrap();
+    let manifest: Value =
+        serde_json::from_str(&stdout(&genesisdb(&source, &["export", "/tenant/42", "--file", path]).await)).unwrap();
+    assert_eq!(manifest[0]["count"], 1);
+
+    let target = FakeServer::start().await.unwrap();
+    let summary = stdout(&genesisdb(&target, &["import", "--file", path, "--dry-run", "-o", "ndjson"]).await);
+    assert_eq!(summary, "{\"dry_run\":true,\"imported\":1,\"skipped\":0}\n");
+    assert!(target.events().is_empty());
+
+    stdout(&genesisdb(&target, &["import", "--file", path]).await);
+    assert_eq!(target.events()[0].subject, "/tenant/42");
+
+    std::fs::remove_file(&file).unwrap();
+    std::fs::remove_file(ArchiveManifest::path_for(&file)).unwrap();
+}
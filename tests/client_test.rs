@@ -1,16 +1,27 @@
 //! Unit tests for the GenesisDB client using mockito
 
-use genesisdb_io_client::{Client, ClientConfig, CommitEvent, CommitEventOptions, Precondition, StreamOptions};
+use futures::StreamExt;
+use genesisdb_io_client::{
+    AnomalyKind, AuditStatus, BulkOptions, Client, ClientConfig, CommitEvent, CommitEventOptions,
+    ConnectionState, Error, ObserverEvent, Precondition, ReconnectPolicy, RetryPolicy, StreamOptions,
+    VerifyOptions,
+};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use mockito::{Matcher, Server};
 use serde_json::json;
 
-fn create_test_client(server_url: &str) -> Client {
-    Client::new(ClientConfig {
+fn server_config(server_url: &str) -> ClientConfig {
+    ClientConfig {
         api_url: server_url.to_string(),
         api_version: "v1".to_string(),
         auth_token: "test-token".to_string(),
-    })
-    .unwrap()
+    }
+}
+
+fn create_test_client(server_url: &str) -> Client {
+    Client::new(server_config(server_url)).unwrap()
 }
 
 #[tokio::test]
@@ -38,9 +49,11 @@ async fn test_ping_error() {
     let mock = server
         .mock("GET", "/api/v1/status/ping")
         .with_status(503)
+        .expect(3)
         .create_async()
         .await;
 
+    // Retried with the default policy
     let client = create_test_client(&server.url());
     let result = client.ping().await;
 
@@ -67,6 +80,71 @@ async fn test_audit_success() {
     assert_eq!(result.unwrap(), "Audit successful");
 }
 
+#[tokio::test]
+async fn test_audit_report_failure() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/api/v1/status/audit")
+        .with_status(200)
+        .with_body("Audit failed\nevents: 12\n✓ event ids\n✗ hash chain: broken at event 7\n")
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let report = client.audit_report().await.unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(report.status, AuditStatus::Failed);
+    assert_eq!(report.counts["events"], 12);
+    assert_eq!(report.checks.len(), 2);
+    assert!(report.raw.starts_with("Audit failed"));
+
+    let error = report.ensure_passed().unwrap_err();
+    assert_eq!(error.to_string(), "Audit failed: hash chain");
+}
+
+#[tokio::test]
+async fn test_verify_events_reports_anomalies() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/v1/stream")
+        .with_status(200)
+        .with_body(concat!(
+            "{\"id\":\"a\",\"source\":\"s\",\"specversion\":\"1.0\",\"subject\":\"/user\",\"type\":\"t\",",
+            "\"time\":\"2026-10-16T08:00:00Z\",\"hash\":\"h1\"}\n",
+            "{\"id\":\"a\",\"source\":\"s\",\"specversion\":\"1.0\",\"subject\":\"/user\",\"type\":\"t\",",
+            "\"time\":\"2026-10-16T08:00:01Z\",\"hash\":\"h2\",\"predecessorhash\":\"h0\"}\n",
+        ))
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let report = client
+        .verify_events(
+            "/user",
+            None,
+            &VerifyOptions {
+                contiguous_chain: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(report.events_checked, 2);
+    assert_eq!(
+        report.anomalies.iter().map(|a| &a.kind).collect::<Vec<_>>(),
+        vec![
+            &AnomalyKind::DuplicateId { first_position: 0 },
+            &AnomalyKind::BrokenChain {
+                expected: Some("h1".to_string()),
+                found: Some("h0".to_string()),
+            },
+        ]
+    );
+}
+
 #[tokio::test]
 async fn test_stream_events_success() {
     let mut server = Server::new_async().await;
@@ -191,6 +269,82 @@ async fn test_stream_events_multiple_events() {
     assert_eq!(events[1].id, "2");
 }
 
+#[tokio::test]
+async fn test_stream_events_iter_yields_each_event() {
+    let mut server = Server::new_async().await;
+
+    let event1 = json!({
+        "id": "1",
+        "source": "test",
+        "type": "test.event",
+        "subject": "/test",
+        "data": { "n": 1 }
+    });
+    let event2 = json!({
+        "id": "2",
+        "source": "test",
+        "type": "test.event",
+        "subject": "/test",
+        "data": { "n": 2 }
+    });
+
+    let mock = server
+        .mock("POST", "/api/v1/stream")
+        .match_header("accept", "application/x-ndjson")
+        .match_body(Matcher::Json(json!({ "subject": "/" })))
+        .with_status(200)
+        .with_body(format!("{}\n{}", event1, event2))
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let mut stream = client.stream_events_iter("/", None).await.unwrap();
+
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first.id, "1");
+    let second = stream.next().await.unwrap().unwrap();
+    assert_eq!(second.id, "2");
+    assert!(stream.next().await.is_none());
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_stream_events_iter_invalid_line() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("POST", "/api/v1/stream")
+        .with_status(200)
+        .with_body("not json\n")
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let mut stream = client.stream_events_iter("/test", None).await.unwrap();
+
+    mock.assert_async().await;
+    assert!(matches!(stream.next().await, Some(Err(Error::JsonError(_)))));
+}
+
+#[tokio::test]
+async fn test_stream_events_iter_api_error() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("POST", "/api/v1/stream")
+        .with_status(500)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let result = client.stream_events_iter("/test", None).await;
+
+    mock.assert_async().await;
+    assert!(result.is_err());
+}
+
 #[tokio::test]
 async fn test_stream_events_api_error() {
     let mut server = Server::new_async().await;
@@ -198,6 +352,7 @@ async fn test_stream_events_api_error() {
     let mock = server
         .mock("POST", "/api/v1/stream")
         .with_status(500)
+        .expect(3)
         .create_async()
         .await;
 
@@ -235,7 +390,7 @@ async fn test_commit_events_success() {
                 subject: "/test/subject".to_string(),
                 event_type: "test.event.created".to_string(),
                 data: json!({ "name": "Test Event" }),
-                options: None,
+                ..Default::default()
             }],
             None,
         )
@@ -245,6 +400,162 @@ async fn test_commit_events_success() {
     assert!(result.is_ok());
 }
 
+#[tokio::test]
+async fn test_commit_events_with_client_ids() {
+    let mut server = Server::new_async().await;
+    let id = uuid::Uuid::new_v4();
+
+    let mock = server
+        .mock("POST", "/api/v1/commit")
+        .match_body(Matcher::PartialJson(json!({
+            "events": [{ "id": id.to_string() }, { "source": "test.source" }]
+        })))
+        .with_status(200)
+        .with_body(format!(
+            r#"[{{"id":"{}","time":"2024-01-01T00:00:00Z"}},{{"id":"server-id","time":"2024-01-01T00:00:01Z"}}]"#,
+            id
+        ))
+        .create_async()
+        .await;
+
+    let event = CommitEvent {
+        source: "test.source".to_string(),
+        subject: "/test/subject".to_string(),
+        event_type: "test.event.created".to_string(),
+        data: json!({}),
+        ..Default::default()
+    };
+
+    let client = create_test_client(&server.url());
+    let result = client
+        .commit_events(
+            vec![
+                CommitEvent {
+                    id: Some(id),
+                    ..event.clone()
+                },
+                event,
+            ],
+            None,
+        )
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(result.events[0].id, Some(id.to_string()));
+    assert_eq!(result.events[0].raw_time.as_deref(), Some("2024-01-01T00:00:00Z"));
+    assert!(result.events[0].time.is_some());
+    assert_eq!(result.last_event_id(), Some("server-id"));
+}
+
+#[tokio::test]
+async fn test_commit_events_idempotent_sends_key() {
+    let mut server = Server::new_async().await;
+    let key = genesisdb_io_client::IdempotencyKey::from("order-42");
+
+    let mock = server
+        .mock("POST", "/api/v1/commit")
+        .match_header("idempotency-key", "order-42")
+        .match_body(Matcher::PartialJson(json!({
+            "events": [{ "id": key.event_id(0).to_string() }],
+            "preconditions": [{
+                "type": "isQueryResultTrue",
+                "payload": {
+                    "query": format!(
+                        "STREAM e FROM events WHERE e.subject == '/test/subject' AND e.id == '{}' MAP COUNT() == 0",
+                        key.event_id(0)
+                    )
+                }
+            }]
+        })))
+        .with_status(200)
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let result = client
+        .commit_events_idempotent(
+            vec![CommitEvent {
+                source: "test.source".to_string(),
+                subject: "/test/subject".to_string(),
+                event_type: "test.event.created".to_string(),
+                data: json!({}),
+                ..Default::default()
+            }],
+            None,
+            &key,
+        )
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(result.last_event_id(), Some(key.event_id(0).to_string().as_str()));
+
+    let invalid = genesisdb_io_client::IdempotencyKey::from("order\n42");
+    let result = client.commit_events_idempotent(Vec::new(), None, &invalid).await;
+    assert!(matches!(result, Err(Error::InvalidConfig(_))));
+}
+
+#[tokio::test]
+async fn test_commit_events_bulk_resumes_after_failed_chunk() {
+    let mut server = Server::new_async().await;
+
+    let first_chunks = server
+        .mock("POST", "/api/v1/commit")
+        .with_status(200)
+        .expect(2)
+        .create_async()
+        .await;
+    let failed_chunk = server
+        .mock("POST", "/api/v1/commit")
+        .with_status(400)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let events: Vec<CommitEvent> = (0..5)
+        .map(|i| CommitEvent {
+            source: "test.source".to_string(),
+            subject: "/test/subject".to_string(),
+            event_type: "test.event.created".to_string(),
+            data: json!({ "n": i }),
+            ..Default::default()
+        })
+        .collect();
+    let options = BulkOptions {
+        max_events_per_chunk: 2,
+        ..Default::default()
+    };
+
+    let checkpoint = match client.commit_events_bulk(events.clone(), &options).await {
+        Err(Error::BulkCommitFailed { checkpoint, source }) => {
+            assert!(matches!(*source, Error::ApiError { status: 400, .. }));
+            checkpoint
+        }
+        other => panic!("expected BulkCommitFailed, got {:?}", other),
+    };
+    first_chunks.assert_async().await;
+    failed_chunk.assert_async().await;
+    assert_eq!(checkpoint.committed_ranges(), vec![0..2, 2..4]);
+
+    let resumed = server
+        .mock("POST", "/api/v1/commit")
+        .match_body(Matcher::PartialJson(json!({ "events": [{ "data": { "n": 4 } }] })))
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let result = client
+        .resume_commit_events_bulk(events, &checkpoint, &options)
+        .await
+        .unwrap();
+
+    resumed.assert_async().await;
+    assert_eq!(result.events.len(), 5);
+}
+
 #[tokio::test]
 async fn test_commit_events_with_options() {
     let mut server = Server::new_async().await;
@@ -275,6 +586,7 @@ async fn test_commit_events_with_options() {
                 options: Some(CommitEventOptions {
                     store_data_as_reference: Some(true),
                 }),
+                ..Default::default()
             }],
             None,
         )
@@ -314,12 +626,9 @@ async fn test_commit_events_with_preconditions() {
                 subject: "/test/subject".to_string(),
                 event_type: "test.event.created".to_string(),
                 data: json!({ "name": "Test Event" }),
-                options: None,
+                ..Default::default()
             }],
-            Some(vec![Precondition {
-                precondition_type: "isSubjectNew".to_string(),
-                payload: json!({ "subject": "/test/subject" }),
-            }]),
+            Some(vec![Precondition::is_subject_new("/test/subject")]),
         )
         .await;
 
@@ -345,7 +654,7 @@ async fn test_commit_events_api_error() {
                 subject: "/test".to_string(),
                 event_type: "test.event".to_string(),
                 data: json!({}),
-                options: None,
+                ..Default::default()
             }],
             None,
         )
@@ -355,6 +664,97 @@ async fn test_commit_events_api_error() {
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn test_commit_events_precondition_failed() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("POST", "/api/v1/commit")
+        .with_status(412)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "message": "precondition failed",
+                "failed": [{ "type": "isSubjectNew", "payload": { "subject": "/test" } }]
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let result = client
+        .commit_events(
+            vec![CommitEvent {
+                source: "test".to_string(),
+                subject: "/test".to_string(),
+                event_type: "test.event".to_string(),
+                data: json!({}),
+                ..Default::default()
+            }],
+            Some(vec![Precondition::is_subject_new("/test")]),
+        )
+        .await;
+
+    mock.assert_async().await;
+    match result {
+        Err(Error::PreconditionFailed { failed, message }) => {
+            assert_eq!(message, "precondition failed");
+            assert_eq!(failed, vec![Precondition::is_subject_new("/test")]);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limited_with_retry_after() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("GET", "/api/v1/status/ping")
+        .with_status(429)
+        .with_header("retry-after", "7")
+        .with_body("slow down")
+        .create_async()
+        .await;
+
+    let client = Client::builder(server_config(&server.url()))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    let result = client.ping().await;
+
+    mock.assert_async().await;
+    match result {
+        Err(Error::RateLimited { retry_after, message }) => {
+            assert_eq!(retry_after, Some(Duration::from_secs(7)));
+            assert_eq!(message, "slow down");
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_unauthorized() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("GET", "/api/v1/status/audit")
+        .with_status(401)
+        .with_body(json!({ "error": "invalid token" }).to_string())
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let result = client.audit().await;
+
+    mock.assert_async().await;
+    assert!(matches!(
+        result,
+        Err(Error::Unauthorized { status: 401, message }) if message == "invalid token"
+    ));
+}
+
 #[tokio::test]
 async fn test_erase_data_success() {
     let mut server = Server::new_async().await;
@@ -388,7 +788,7 @@ async fn test_erase_data_api_error() {
     let result = client.erase_data("/test/subject").await;
 
     mock.assert_async().await;
-    assert!(result.is_err());
+    assert!(matches!(result, Err(Error::NotFound { .. })));
 }
 
 #[tokio::test]
@@ -437,6 +837,57 @@ async fn test_query_empty_results() {
     assert_eq!(result.unwrap().len(), 0);
 }
 
+#[tokio::test]
+async fn test_query_stream() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("POST", "/api/v1/q")
+        .with_status(200)
+        .with_body("{\"id\":\"1\"}\n{\"id\":\"2\"}\n")
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let mut rows = client.q_stream("FROM e IN events PROJECT INTO { id: e.id }").await.unwrap();
+
+    assert_eq!(rows.next().await.unwrap().unwrap(), json!({ "id": "1" }));
+    assert_eq!(rows.next().await.unwrap().unwrap(), json!({ "id": "2" }));
+    assert!(rows.next().await.is_none());
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_query_as_reports_failing_line() {
+    #[derive(Debug, serde::Deserialize)]
+    struct Row {
+        id: String,
+    }
+
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("POST", "/api/v1/q")
+        .with_status(200)
+        .with_body("{\"id\":\"1\"}\n\n{\"id\":2}\n{\"id\":\"3\"}\n")
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let rows: Vec<Result<Row, Error>> = client
+        .q_as::<Row>("FROM e IN events PROJECT INTO { id: e.id }")
+        .await
+        .unwrap()
+        .collect()
+        .await;
+
+    mock.assert_async().await;
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].as_ref().unwrap().id, "1");
+    assert!(matches!(rows[1], Err(Error::RowParse { line: 3, .. })));
+    assert_eq!(rows[2].as_ref().unwrap().id, "3");
+}
+
 #[tokio::test]
 async fn test_query_api_error() {
     let mut server = Server::new_async().await;
@@ -476,3 +927,269 @@ async fn test_query_events_calls_q() {
     let results = result.unwrap();
     assert_eq!(results.len(), 1);
 }
+
+fn fast_reconnect_policy(max_attempts: Option<u32>) -> ReconnectPolicy {
+    ReconnectPolicy {
+        max_attempts,
+        initial_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(10),
+        multiplier: 1.0,
+    }
+}
+
+#[tokio::test]
+async fn test_observe_events_resilient_resumes_after_last_event() {
+    let mut server = Server::new_async().await;
+
+    let event1 = json!({ "id": "1", "source": "test", "type": "test.event", "subject": "/test" });
+    let event2 = json!({ "id": "2", "source": "test", "type": "test.event", "subject": "/test" });
+
+    let first = server
+        .mock("POST", "/api/v1/observe")
+        .match_body(Matcher::Json(json!({ "subject": "/test" })))
+        .with_status(200)
+        .with_body(format!("{}\n", event1))
+        .create_async()
+        .await;
+    let resumed = server
+        .mock("POST", "/api/v1/observe")
+        .match_body(Matcher::Json(json!({
+            "subject": "/test",
+            "options": { "lowerBound": "1", "includeLowerBoundEvent": false }
+        })))
+        .with_status(200)
+        .with_body(format!("{}\n", event2))
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let items: Vec<ObserverEvent> = client
+        .observe_events_resilient("/test", None, fast_reconnect_policy(None))
+        .take(5)
+        .map(|item| item.unwrap())
+        .collect()
+        .await;
+
+    first.assert_async().await;
+    resumed.assert_async().await;
+
+    assert!(matches!(&items[0], ObserverEvent::StateChanged(ConnectionState::Connected)));
+    assert!(matches!(&items[1], ObserverEvent::Event(e) if e.id == "1"));
+    assert!(matches!(
+        &items[2],
+        ObserverEvent::StateChanged(ConnectionState::Reconnecting { attempt: 1, .. })
+    ));
+    assert!(matches!(&items[3], ObserverEvent::StateChanged(ConnectionState::Connected)));
+    assert!(matches!(&items[4], ObserverEvent::Event(e) if e.id == "2"));
+}
+
+#[tokio::test]
+async fn test_observe_events_resilient_gives_up() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("POST", "/api/v1/observe")
+        .with_status(503)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let items: Vec<_> = client
+        .observe_events_resilient("/test", None, fast_reconnect_policy(Some(2)))
+        .collect()
+        .await;
+
+    mock.assert_async().await;
+    assert_eq!(items.len(), 4);
+    assert!(matches!(
+        items[0],
+        Ok(ObserverEvent::StateChanged(ConnectionState::Reconnecting { attempt: 1, .. }))
+    ));
+    assert!(matches!(
+        items[1],
+        Ok(ObserverEvent::StateChanged(ConnectionState::Reconnecting { attempt: 2, .. }))
+    ));
+    assert!(matches!(items[2], Ok(ObserverEvent::StateChanged(ConnectionState::GaveUp))));
+    assert!(matches!(items[3], Err(Error::ServerError { status: 503, .. })));
+}
+
+#[tokio::test]
+async fn test_observe_events_resilient_stops_on_client_error() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("POST", "/api/v1/observe")
+        .with_status(401)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let items: Vec<_> = client
+        .observe_events_resilient("/test", None, fast_reconnect_policy(None))
+        .collect()
+        .await;
+
+    mock.assert_async().await;
+    assert_eq!(items.len(), 2);
+    assert!(matches!(items[0], Ok(ObserverEvent::StateChanged(ConnectionState::GaveUp))));
+    assert!(matches!(items[1], Err(Error::Unauthorized { status: 401, .. })));
+}
+
+fn create_retrying_client(server_url: &str, retries: Arc<AtomicU32>) -> Client {
+    Client::builder(server_config(server_url))
+        .retry_policy(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            on_retry: Some(Arc::new(move |_| {
+                retries.fetch_add(1, Ordering::SeqCst);
+            })),
+            ..Default::default()
+        })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_retry_transient_errors() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("GET", "/api/v1/status/ping")
+        .with_status(503)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let retries = Arc::new(AtomicU32::new(0));
+    let client = create_retrying_client(&server.url(), retries.clone());
+    let result = client.ping().await;
+
+    mock.assert_async().await;
+    assert!(matches!(result, Err(Error::ServerError { status: 503, .. })));
+    assert_eq!(retries.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_default_client_retries_reads() {
+    let mut server = Server::new_async().await;
+
+    let unavailable = server
+        .mock("GET", "/api/v1/status/ping")
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    let pong = server
+        .mock("GET", "/api/v1/status/ping")
+        .with_status(200)
+        .with_body("pong")
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let result = client.ping().await;
+
+    unavailable.assert_async().await;
+    pong.assert_async().await;
+    assert_eq!(result.unwrap(), "pong");
+}
+
+#[tokio::test]
+async fn test_retry_skips_client_errors() {
+    let mut server = Server::new_async().await;
+
+    let mock = server
+        .mock("POST", "/api/v1/q")
+        .with_status(400)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let retries = Arc::new(AtomicU32::new(0));
+    let client = create_retrying_client(&server.url(), retries.clone());
+    let result = client.q("INVALID QUERY").await;
+
+    mock.assert_async().await;
+    assert!(result.is_err());
+    assert_eq!(retries.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_retry_commit_only_with_excluding_preconditions() {
+    let mut server = Server::new_async().await;
+
+    let event = CommitEvent {
+        source: "test".to_string(),
+        subject: "/test".to_string(),
+        event_type: "test.event".to_string(),
+        data: json!({}),
+        ..Default::default()
+    };
+
+    let unguarded = server
+        .mock("POST", "/api/v1/commit")
+        .match_body(Matcher::PartialJson(json!({ "events": [{ "subject": "/test" }] })))
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let retries = Arc::new(AtomicU32::new(0));
+    let client = create_retrying_client(&server.url(), retries.clone());
+    let result = client.commit_events(vec![event.clone()], None).await;
+
+    unguarded.assert_async().await;
+    assert!(result.is_err());
+    assert_eq!(retries.load(Ordering::SeqCst), 0);
+    unguarded.remove_async().await;
+
+    // A precondition that still holds after the write does not exclude duplicates
+    let not_excluding = server
+        .mock("POST", "/api/v1/commit")
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let result = client
+        .commit_events(vec![event.clone()], Some(vec![Precondition::is_subject_existing("/test")]))
+        .await;
+
+    not_excluding.assert_async().await;
+    assert!(result.is_err());
+    assert_eq!(retries.load(Ordering::SeqCst), 0);
+    not_excluding.remove_async().await;
+
+    let guarded = server
+        .mock("POST", "/api/v1/commit")
+        .with_status(503)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let result = client
+        .commit_events(vec![event.clone()], Some(vec![Precondition::is_subject_new("/test")]))
+        .await;
+
+    guarded.assert_async().await;
+    assert!(result.is_err());
+    assert_eq!(retries.load(Ordering::SeqCst), 2);
+    guarded.remove_async().await;
+
+    let opted_in = server
+        .mock("POST", "/api/v1/commit")
+        .with_status(503)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let guard = Precondition::is_query_result_true("STREAM e FROM events WHERE e.subject == '/test' MAP COUNT() == 0");
+    let result = client.commit_events_with_retry(vec![event], vec![guard]).await;
+
+    opted_in.assert_async().await;
+    assert!(result.is_err());
+    assert_eq!(retries.load(Ordering::SeqCst), 4);
+}
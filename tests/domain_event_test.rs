@@ -0,0 +1,149 @@
+//! Tests for typed domain events
+//!
+//! Run with: cargo test --features derive --test domain_event_test
+
+#![cfg(feature = "derive")]
+
+use genesisdb_io_client::{Client, ClientConfig, CloudEvent, DomainEvent, Error, EventSet};
+use mockito::{Matcher, Server};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, DomainEvent)]
+#[domain_event(type = "io.genesisdb.app.user-created", source = "io.genesisdb.app")]
+struct UserCreated {
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, DomainEvent)]
+#[domain_event(type = "io.genesisdb.app.user-renamed", source = "io.genesisdb.app")]
+struct UserRenamed {
+    name: String,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, PartialEq, EventSet)]
+enum UserEvent {
+    Created(UserCreated),
+    Renamed(UserRenamed),
+    #[domain_event(unknown)]
+    Unknown(CloudEvent),
+}
+
+#[derive(Debug, PartialEq, EventSet)]
+enum StrictUserEvent {
+    Created(UserCreated),
+}
+
+fn create_test_client(server_url: &str) -> Client {
+    Client::new(ClientConfig {
+        api_url: server_url.to_string(),
+        api_version: "v1".to_string(),
+        auth_token: "test-token".to_string(),
+    })
+    .unwrap()
+}
+
+fn cloud_event(event_type: &str, data: serde_json::Value) -> CloudEvent {
+    serde_json::from_value(json!({
+        "id": "1",
+        "source": "io.genesisdb.app",
+        "subject": "/user/1",
+        "type": event_type,
+        "data": data,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_domain_event_constants() {
+    assert_eq!(UserCreated::EVENT_TYPE, "io.genesisdb.app.user-created");
+    assert_eq!(UserCreated::SOURCE, "io.genesisdb.app");
+
+    let event = UserCreated { name: "Jane".to_string() }
+        .to_commit_event("/user/1")
+        .unwrap();
+    assert_eq!(event.event_type, "io.genesisdb.app.user-created");
+    assert_eq!(event.data, json!({ "name": "Jane" }));
+}
+
+#[test]
+fn test_decode_event_set() {
+    let created = cloud_event("io.genesisdb.app.user-created", json!({ "name": "Jane" }));
+    assert_eq!(
+        UserEvent::decode(&created).unwrap(),
+        UserEvent::Created(UserCreated { name: "Jane".to_string() })
+    );
+
+    let other = cloud_event("io.genesisdb.app.user-deleted", json!({}));
+    assert!(matches!(UserEvent::decode(&other).unwrap(), UserEvent::Unknown(e) if e.id == "1"));
+    assert!(matches!(
+        StrictUserEvent::decode(&other),
+        Err(Error::UnknownEventType(t)) if t == "io.genesisdb.app.user-deleted"
+    ));
+
+    let malformed = cloud_event("io.genesisdb.app.user-created", json!({ "name": 1 }));
+    assert!(matches!(UserEvent::decode(&malformed), Err(Error::JsonError(_))));
+}
+
+#[tokio::test]
+async fn test_commit_domain_events() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/v1/commit")
+        .match_body(Matcher::Json(json!({
+            "events": [
+                {
+                    "source": "io.genesisdb.app",
+                    "subject": "/user/1",
+                    "type": "io.genesisdb.app.user-created",
+                    "data": { "name": "Jane" }
+                },
+                {
+                    "source": "io.genesisdb.app",
+                    "subject": "/user/1",
+                    "type": "io.genesisdb.app.user-renamed",
+                    "data": { "name": "Janet" }
+                }
+            ]
+        })))
+        .with_status(200)
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let events = vec![
+        UserEvent::Created(UserCreated { name: "Jane".to_string() }),
+        UserEvent::Renamed(UserRenamed { name: "Janet".to_string() }),
+    ];
+    client.commit_domain_events("/user/1", &events, None).await.unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_stream_domain_events() {
+    let mut server = Server::new_async().await;
+    let body = [
+        json!({ "id": "1", "source": "io.genesisdb.app", "subject": "/user/1", "type": "io.genesisdb.app.user-created", "data": { "name": "Jane" } }),
+        json!({ "id": "2", "source": "io.genesisdb.app", "subject": "/user/1", "type": "io.genesisdb.app.user-deleted" }),
+    ]
+    .iter()
+    .map(|e| e.to_string())
+    .collect::<Vec<_>>()
+    .join("\n");
+    let mock = server
+        .mock("POST", "/api/v1/stream")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    let events: Vec<UserEvent> = client.stream_domain_events("/user/1", None).await.unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0], UserEvent::Created(UserCreated { name: "Jane".to_string() }));
+    assert!(matches!(&events[1], UserEvent::Unknown(e) if e.id == "2"));
+}
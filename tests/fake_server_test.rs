@@ -0,0 +1,631 @@
+//! Tests for the in-memory fake server
+//!
+//! Run with: cargo test --features testing --test fake_server_test
+
+#![cfg(feature = "testing")]
+
+use futures::StreamExt;
+use genesisdb_io_client::testing::FakeServer;
+use genesisdb_io_client::{
+    ArchiveManifest, BulkOptions, Client, ClientConfig, CommitEvent, CommitEventOptions, Compression,
+    Error, ExportOptions, IdempotencyKey, ImportOptions, Precondition, RetryPolicy, StreamOptions, VerifyOptions,
+};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn event(subject: &str, event_type: &str, data: serde_json::Value) -> CommitEvent {
+    CommitEvent {
+        source: "io.genesisdb.test".to_string(),
+        subject: subject.to_string(),
+        event_type: event_type.to_string(),
+        data,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_commit_then_stream() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+
+    client
+        .commit_events(
+            vec![
+                event("/user/1", "created", json!({ "n": 1 })),
+                event("/user/2", "created", json!({ "n": 2 })),
+                event("/user/1", "updated", json!({ "n": 3 })),
+            ],
+            None,
+        )
+        .await
+        .unwrap();
+
+    let all = client.stream_events("/", None).await.unwrap();
+    assert_eq!(all.len(), 3);
+    assert!(all.iter().all(|e| e.time.is_some() && !e.id.is_empty()));
+
+    let user1 = client.stream_events("/user/1", None).await.unwrap();
+    assert_eq!(user1.len(), 2);
+
+    let after_first = client
+        .stream_events(
+            "/",
+            Some(StreamOptions {
+                lower_bound: Some(all[0].id.clone()),
+                include_lower_bound_event: Some(false),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(after_first.len(), 2);
+    assert_eq!(after_first[0].id, all[1].id);
+}
+
+#[tokio::test]
+async fn test_commit_result_and_client_ids() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+    let id = uuid::Uuid::new_v4();
+
+    let mut first = event("/user/1", "created", json!({}));
+    first.id = Some(id);
+    let result = client
+        .commit_events(vec![first.clone(), event("/user/1", "updated", json!({}))], None)
+        .await
+        .unwrap();
+
+    let stored = server.events();
+    assert_eq!(result.events.len(), 2);
+    assert_eq!(result.events[0].id, Some(id.to_string()));
+    assert_eq!(result.last_event_id(), Some(stored[1].id.as_str()));
+    assert_eq!(result.events[1].time, stored[1].time);
+    assert_eq!(result.events[1].raw_time, stored[1].raw_time);
+
+    let duplicate = client.commit_events(vec![first], None).await;
+    assert!(matches!(duplicate, Err(Error::ApiError { status: 409, .. })));
+    assert_eq!(server.events().len(), 2);
+}
+
+#[tokio::test]
+async fn test_idempotent_commit_writes_once() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+    let key = IdempotencyKey::new();
+    let events = vec![
+        event("/order/1", "placed", json!({})),
+        event("/order/1", "paid", json!({})),
+    ];
+
+    let first = client
+        .commit_events_idempotent(events.clone(), None, &key)
+        .await
+        .unwrap();
+    let second = client
+        .commit_events_idempotent(events.clone(), None, &key)
+        .await
+        .unwrap();
+
+    assert_eq!(server.events().len(), 2);
+    assert_eq!(first, second);
+    assert_eq!(first.events[0].id, Some(key.event_id(0).to_string()));
+
+    // A rejection unrelated to the key is still reported
+    let result = client
+        .commit_events_idempotent(
+            events,
+            Some(vec![Precondition::is_subject_new("/order/1")]),
+            &IdempotencyKey::new(),
+        )
+        .await;
+    assert!(matches!(result, Err(Error::PreconditionFailed { .. })));
+    assert_eq!(server.events().len(), 2);
+}
+
+#[tokio::test]
+async fn test_commit_events_bulk_resumes_after_lost_response() {
+    let server = FakeServer::start().await.unwrap();
+    // Without retries, so the lost response fails the chunk
+    let client = Client::builder(server.config()).retry_policy(RetryPolicy::none()).build().unwrap();
+    let events: Vec<CommitEvent> = (0..5)
+        .map(|n| event("/import", "imported", json!({ "n": n })))
+        .collect();
+    let options = BulkOptions {
+        max_events_per_chunk: 2,
+        ..Default::default()
+    };
+
+    // The first chunk is stored, but reported as failed
+    server.lose_commit_responses(1);
+    let checkpoint = match client.commit_events_bulk(events.clone(), &options).await {
+        Err(Error::BulkCommitFailed { checkpoint, source }) => {
+            assert!(matches!(*source, Error::ServerError { status: 503, .. }));
+            checkpoint
+        }
+        other => panic!("expected BulkCommitFailed, got {:?}", other),
+    };
+    assert_eq!(checkpoint.committed_events(), 0);
+    assert_eq!(server.events().len(), 2);
+
+    let result = client
+        .resume_commit_events_bulk(events, &checkpoint, &options)
+        .await
+        .unwrap();
+
+    let stored = server.events();
+    assert_eq!(stored.len(), 5);
+    let ids: Vec<_> = stored.iter().map(|event| Some(event.id.clone())).collect();
+    assert_eq!(result.events.iter().map(|event| event.id.clone()).collect::<Vec<_>>(), ids);
+}
+
+#[tokio::test]
+async fn test_commit_events_bulk_with_concurrency() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+    let events: Vec<CommitEvent> = (0..10)
+        .map(|n| event("/import", "imported", json!({ "n": n })))
+        .collect();
+
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let options = BulkOptions {
+        max_events_per_chunk: 3,
+        concurrency: 3,
+        on_progress: Some({
+            let progress = progress.clone();
+            Arc::new(move |p| progress.lock().unwrap().push((p.chunk.clone(), p.committed, p.total)))
+        }),
+        ..Default::default()
+    };
+
+    let result = client.commit_events_bulk(events, &options).await.unwrap();
+
+    let stored = server.events();
+    assert_eq!(stored.len(), 10);
+    assert_eq!(result.events.len(), 10);
+    // The result follows the order of the batch, not the order chunks finished in
+    for event in &stored {
+        let n = event.data.as_ref().unwrap()["n"].as_u64().unwrap() as usize;
+        assert_eq!(result.events[n].id.as_deref(), Some(event.id.as_str()));
+    }
+
+    let mut progress = progress.lock().unwrap().clone();
+    assert_eq!(progress.len(), 4);
+    assert_eq!(progress.last().map(|p| (p.1, p.2)), Some((10, 10)));
+    progress.sort_by_key(|p| p.0.start);
+    let chunks: Vec<_> = progress.into_iter().map(|p| p.0).collect();
+    assert_eq!(chunks, vec![0..3, 3..6, 6..9, 9..10]);
+}
+
+#[tokio::test]
+async fn test_preconditions() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+
+    client
+        .commit_events(
+            vec![event("/user/1", "created", json!({}))],
+            Some(vec![Precondition::is_subject_new("/user/1")]),
+        )
+        .await
+        .unwrap();
+
+    let result = client
+        .commit_events(
+            vec![event("/user/1", "created", json!({}))],
+            Some(vec![Precondition::is_subject_new("/user/1")]),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::PreconditionFailed { failed, .. }) if failed == vec![Precondition::is_subject_new("/user/1")]
+    ));
+
+    let result = client
+        .commit_events(
+            vec![event("/user/2", "updated", json!({}))],
+            Some(vec![Precondition::is_subject_existing("/user/2")]),
+        )
+        .await;
+    assert!(matches!(result, Err(Error::PreconditionFailed { .. })));
+    assert_eq!(server.events().len(), 1);
+}
+
+#[tokio::test]
+async fn test_observe_receives_live_events() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+
+    client
+        .commit_events(vec![event("/user/1", "created", json!({}))], None)
+        .await
+        .unwrap();
+
+    let mut stream = client.observe_events("/user", None).await.unwrap();
+    let historic = stream.next().await.unwrap().unwrap();
+    assert_eq!(historic.event_type, "created");
+
+    client
+        .commit_events(
+            vec![
+                event("/order/1", "created", json!({})),
+                event("/user/1", "updated", json!({})),
+            ],
+            None,
+        )
+        .await
+        .unwrap();
+
+    let live = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(live.subject, "/user/1");
+    assert_eq!(live.event_type, "updated");
+}
+
+#[tokio::test]
+async fn test_observe_ends_when_observer_lags() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+
+    let stream = client.observe_events("/user", None).await.unwrap();
+    let events = (0..1100).map(|i| event("/user/1", "created", json!({ "n": i }))).collect();
+    client.commit_events(events, None).await.unwrap();
+
+    let received: Vec<_> = tokio::time::timeout(Duration::from_secs(5), stream.collect())
+        .await
+        .expect("observe stream ends instead of skipping events");
+    assert!(received.len() < 1100);
+}
+
+#[tokio::test]
+async fn test_erase_referenced_data() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+
+    let mut referenced = event("/user/1", "created", json!({ "email": "a@example.com" }));
+    referenced.options = Some(CommitEventOptions {
+        store_data_as_reference: Some(true),
+    });
+    client.commit_events(vec![referenced], None).await.unwrap();
+
+    client.erase_data("/user/1").await.unwrap();
+
+    let events = client.stream_events("/user/1", None).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].data.is_none());
+}
+
+#[tokio::test]
+async fn test_query_and_query_precondition() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+
+    client
+        .commit_events(
+            vec![event("/user/1", "created", json!({ "email": "a@example.com" }))],
+            None,
+        )
+        .await
+        .unwrap();
+
+    let rows = client
+        .q("FROM e IN events WHERE e.data.email == 'a@example.com' PROJECT INTO { subject: e.subject }")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![json!({ "subject": "/user/1" })]);
+
+    let result = client
+        .commit_events(
+            vec![event("/user/2", "created", json!({ "email": "a@example.com" }))],
+            Some(vec![Precondition::is_query_result_true(
+                "STREAM e FROM events WHERE e.data.email == 'a@example.com' MAP COUNT() == 0",
+            )]),
+        )
+        .await;
+    assert!(matches!(result, Err(Error::PreconditionFailed { .. })));
+
+    let result = client.q("SELECT * FROM events").await;
+    assert!(matches!(result, Err(Error::ApiError { status: 400, .. })));
+}
+
+#[tokio::test]
+async fn test_rejects_invalid_token() {
+    let server = FakeServer::with_token("right").await.unwrap();
+    let mut config = server.config();
+    config.auth_token = "wrong".to_string();
+    let client = genesisdb_io_client::Client::new(config).unwrap();
+
+    assert!(matches!(client.ping().await, Err(Error::Unauthorized { .. })));
+}
+
+fn archive_path(extension: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("genesisdb-archive-{}.{}", uuid::Uuid::new_v4(), extension))
+}
+
+fn remove_archive(path: &std::path::Path) {
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(ArchiveManifest::path_for(path)).unwrap();
+}
+
+async fn export_and_import(compression: Compression, extension: &str) {
+    let source = FakeServer::start().await.unwrap();
+    source
+        .client()
+        .commit_events(
+            vec![
+                event("/tenant/42/user/1", "created", json!({ "n": 1 })),
+                event("/tenant/7/user/1", "created", json!({ "n": 2 })),
+                event("/tenant/42/user/2", "created", json!({ "n": 3 })),
+                event("/tenant/42/user/1", "updated", json!({ "n": 4 })),
+            ],
+            None,
+        )
+        .await
+        .unwrap();
+    let exported = source.client().stream_events("/tenant/42", None).await.unwrap();
+
+    let path = archive_path(extension);
+    let manifest = source
+        .client()
+        .export_events("/tenant/42", &path, &ExportOptions { compression })
+        .await
+        .unwrap();
+    assert_eq!(manifest.count, 3);
+    assert_eq!(manifest.first_event_id.as_deref(), Some(exported[0].id.as_str()));
+    assert_eq!(manifest.last_event_id.as_deref(), Some(exported[2].id.as_str()));
+    assert_eq!(ArchiveManifest::read(&path).await.unwrap(), manifest);
+
+    let target = FakeServer::start().await.unwrap();
+    let summary = target
+        .client()
+        .import_events(&path, &ImportOptions { dry_run: true, ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!((summary.skipped, summary.imported), (0, 3));
+    assert!(target.events().is_empty());
+
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let recorded = progress.clone();
+    let options = ImportOptions {
+        batch_size: 2,
+        on_progress: Some(Arc::new(move |p| recorded.lock().unwrap().push(p.chunk.clone()))),
+        ..Default::default()
+    };
+    target.client().import_events(&path, &options).await.unwrap();
+    assert_eq!(*progress.lock().unwrap(), vec![0..2, 2..3]);
+
+    let imported = target.events();
+    assert_eq!(imported.len(), 3);
+    for (imported, exported) in imported.iter().zip(&exported) {
+        assert_eq!(imported.source, exported.source);
+        assert_eq!(imported.subject, exported.subject);
+        assert_eq!(imported.event_type, exported.event_type);
+        assert_eq!(imported.data, exported.data);
+    }
+
+    remove_archive(&path);
+}
+
+#[tokio::test]
+async fn test_export_and_import() {
+    export_and_import(Compression::None, "ndjson").await;
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn test_export_and_import_gzip() {
+    export_and_import(Compression::Gzip, "ndjson.gz").await;
+}
+
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn test_export_and_import_zstd() {
+    export_and_import(Compression::Zstd, "ndjson.zst").await;
+}
+
+#[tokio::test]
+async fn test_import_resumes_from_offset() {
+    let source = FakeServer::start().await.unwrap();
+    source
+        .client()
+        .commit_events((1..=3).map(|n| event("/user/1", "updated", json!({ "n": n }))).collect(), None)
+        .await
+        .unwrap();
+    let path = archive_path("ndjson");
+    source
+        .client()
+        .export_events("/", &path, &ExportOptions::default())
+        .await
+        .unwrap();
+
+    let target = FakeServer::start().await.unwrap();
+    let summary = target
+        .client()
+        .import_events(&path, &ImportOptions { offset: 2, ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!((summary.skipped, summary.imported), (2, 1));
+    let imported = target.events();
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].data, Some(json!({ "n": 3 })));
+
+    let error = target
+        .client()
+        .import_events(&path, &ImportOptions { offset: 4, ..Default::default() })
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+
+    let unauthorized = Client::new(ClientConfig {
+        auth_token: "wrong-token".to_string(),
+        ..target.config()
+    })
+    .unwrap();
+    let error = unauthorized
+        .import_events(&path, &ImportOptions { offset: 1, ..Default::default() })
+    .await
+    .unwrap_err();
+    assert!(matches!(error, Error::ImportFailed { offset: 1, .. }));
+
+    remove_archive(&path);
+}
+
+#[tokio::test]
+async fn test_import_resume_skips_committed_batch() {
+    let source = FakeServer::start().await.unwrap();
+    source
+        .client()
+        .commit_events((1..=3).map(|n| event("/user/1", "updated", json!({ "n": n }))).collect(), None)
+        .await
+        .unwrap();
+    let path = archive_path("ndjson");
+    source
+        .client()
+        .export_events("/", &path, &ExportOptions::default())
+        .await
+        .unwrap();
+
+    let target = FakeServer::start().await.unwrap();
+    let options = ImportOptions { batch_size: 2, ..Default::default() };
+    target.client().import_events(&path, &options).await.unwrap();
+    let imported = target.events();
+    assert_eq!(imported.len(), 3);
+
+    // Resuming at a batch that was stored although its response was lost commits nothing twice
+    let summary = target
+        .client()
+        .import_events(&path, &ImportOptions { offset: 2, ..options })
+        .await
+        .unwrap();
+    assert_eq!((summary.skipped, summary.imported), (2, 1));
+    assert_eq!(target.events(), imported);
+
+    remove_archive(&path);
+}
+
+#[tokio::test]
+async fn test_import_resume_with_other_batch_size() {
+    let source = FakeServer::start().await.unwrap();
+    source
+        .client()
+        .commit_events((1..=3).map(|n| event("/user/1", "updated", json!({ "n": n }))).collect(), None)
+        .await
+        .unwrap();
+    let path = archive_path("ndjson");
+    source
+        .client()
+        .export_events("/", &path, &ExportOptions::default())
+        .await
+        .unwrap();
+
+    let target = FakeServer::start().await.unwrap();
+    let client = Client::builder(target.config()).retry_policy(RetryPolicy::none()).build().unwrap();
+    target.lose_commit_responses(1);
+    let options = ImportOptions { batch_size: 2, ..Default::default() };
+    let error = client.import_events(&path, &options).await.unwrap_err();
+    assert!(matches!(error, Error::ImportFailed { offset: 0, .. }));
+    assert_eq!(target.events().len(), 2);
+
+    let summary = client
+        .import_events(&path, &ImportOptions { batch_size: 3, ..options })
+        .await
+        .unwrap();
+    assert_eq!(summary.imported, 3);
+    let imported = target.events();
+    assert_eq!(imported.len(), 3);
+    let values: Vec<_> = imported.iter().map(|event| event.data.as_ref().unwrap()["n"].clone()).collect();
+    assert_eq!(values, vec![json!(1), json!(2), json!(3)]);
+
+    remove_archive(&path);
+}
+
+#[tokio::test]
+async fn test_import_rejects_tampered_archive() {
+    let server = FakeServer::start().await.unwrap();
+    server
+        .client()
+        .commit_events(vec![event("/user/1", "created", json!({ "name": "Bruce" }))], None)
+        .await
+        .unwrap();
+    let path = archive_path("ndjson");
+    server
+        .client()
+        .export_events("/", &path, &ExportOptions::default())
+        .await
+        .unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, content.replace("Bruce", "Alfred")).unwrap();
+    let error = server
+        .client()
+        .import_events(&path, &ImportOptions::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidArchive(message) if message.contains("checksum")));
+    assert_eq!(server.events().len(), 1);
+
+    std::fs::remove_file(ArchiveManifest::path_for(&path)).unwrap();
+    let error = server
+        .client()
+        .import_events(&path, &ImportOptions::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidArchive(message) if message.contains("missing manifest")));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_verify_events() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+    client
+        .commit_events(
+            vec![
+                event("/user/1", "created", json!({ "n": 1 })),
+                event("/user/2", "created", json!({ "n": 2 })),
+            ],
+            None,
+        )
+        .await
+        .unwrap();
+
+    let report = client
+        .verify_events("/user", None, &VerifyOptions::default())
+        .await
+        .unwrap();
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.events_checked, 2);
+}
+
+#[tokio::test]
+async fn test_extension_attributes_round_trip() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+    client
+        .commit_events(
+            vec![event("/user/1", "created", json!({}))
+                .with_extension("correlationid", "c0ffee")
+                .unwrap()
+                .with_extension("causationid", "cause-1")
+                .unwrap()],
+            None,
+        )
+        .await
+        .unwrap();
+
+    let events = client.stream_events("/user/1", None).await.unwrap();
+    assert_eq!(events[0].extensions["correlationid"], "c0ffee");
+    assert_eq!(events[0].extensions["causationid"], "cause-1");
+    assert!(events[0].validate().is_ok());
+
+    // An extension shadowing an attribute is rejected before anything is sent
+    let mut shadowing = event("/user/2", "created", json!({}));
+    shadowing.extensions.insert("subject".to_string(), json!("/admin"));
+    let error = client.commit_events(vec![shadowing], None).await.unwrap_err();
+    assert!(matches!(error, Error::InvalidEvent { .. }));
+    assert_eq!(server.events().len(), 1);
+}
@@ -7,18 +7,50 @@
 //! - GENESISDB_AUTH_TOKEN
 //!
 //! Or run with: GENESISDB_INTEGRATION_TESTS=1 cargo test --test integration_test
+//!
+//! Without a real server they run against the in-memory fake server:
+//! cargo test --features testing --test integration_test
 
 use genesisdb_io_client::{Client, ClientConfig, CommitEvent, Precondition};
 use serde_json::json;
 use std::env;
+use std::ops::Deref;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "testing")]
+use genesisdb_io_client::testing::FakeServer;
+
 fn should_run_integration_tests() -> bool {
     env::var("GENESISDB_INTEGRATION_TESTS").is_ok()
 }
 
-fn create_integration_client() -> Option<Client> {
+/// Client for the integration tests, keeping the fake server alive if one is used
+struct IntegrationClient {
+    client: Client,
+    #[cfg(feature = "testing")]
+    _server: Option<FakeServer>,
+}
+
+impl Deref for IntegrationClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+async fn create_integration_client() -> Option<IntegrationClient> {
     if !should_run_integration_tests() {
+        #[cfg(feature = "testing")]
+        {
+            let server = FakeServer::start().await.unwrap();
+            return Some(IntegrationClient {
+                client: server.client(),
+                _server: Some(server),
+            });
+        }
+
+        #[cfg(not(feature = "testing"))]
         return None;
     }
 
@@ -29,14 +61,16 @@ fn create_integration_client() -> Option<Client> {
     let auth_token = env::var("GENESISDB_AUTH_TOKEN")
         .unwrap_or_else(|_| "secret".to_string());
 
-    Some(
-        Client::new(ClientConfig {
+    Some(IntegrationClient {
+        client: Client::new(ClientConfig {
             api_url,
             api_version,
             auth_token,
         })
         .unwrap(),
-    )
+        #[cfg(feature = "testing")]
+        _server: None,
+    })
 }
 
 fn get_timestamp() -> u128 {
@@ -48,7 +82,7 @@ fn get_timestamp() -> u128 {
 
 #[tokio::test]
 async fn test_integration_ping() {
-    let client = match create_integration_client() {
+    let client = match create_integration_client().await {
         Some(c) => c,
         None => {
             println!("Skipping integration test: GENESISDB_INTEGRATION_TESTS not set");
@@ -65,7 +99,7 @@ async fn test_integration_ping() {
 
 #[tokio::test]
 async fn test_integration_audit() {
-    let client = match create_integration_client() {
+    let client = match create_integration_client().await {
         Some(c) => c,
         None => {
             println!("Skipping integration test: GENESISDB_INTEGRATION_TESTS not set");
@@ -82,7 +116,7 @@ async fn test_integration_audit() {
 
 #[tokio::test]
 async fn test_integration_commit_and_stream_events() {
-    let client = match create_integration_client() {
+    let client = match create_integration_client().await {
         Some(c) => c,
         None => {
             println!("Skipping integration test: GENESISDB_INTEGRATION_TESTS not set");
@@ -105,7 +139,7 @@ async fn test_integration_commit_and_stream_events() {
                     "uniqueId": unique_id,
                     "timestamp": get_timestamp()
                 }),
-                options: None,
+                ..Default::default()
             }],
             None,
         )
@@ -136,7 +170,7 @@ async fn test_integration_commit_and_stream_events() {
 
 #[tokio::test]
 async fn test_integration_query() {
-    let client = match create_integration_client() {
+    let client = match create_integration_client().await {
         Some(c) => c,
         None => {
             println!("Skipping integration test: GENESISDB_INTEGRATION_TESTS not set");
@@ -167,7 +201,7 @@ async fn test_integration_query() {
 
 #[tokio::test]
 async fn test_integration_commit_with_preconditions() {
-    let client = match create_integration_client() {
+    let client = match create_integration_client().await {
         Some(c) => c,
         None => {
             println!("Skipping integration test: GENESISDB_INTEGRATION_TESTS not set");
@@ -189,12 +223,9 @@ async fn test_integration_commit_with_preconditions() {
                     "message": "Precondition test event",
                     "uniqueId": unique_id
                 }),
-                options: None,
+                ..Default::default()
             }],
-            Some(vec![Precondition {
-                precondition_type: "isSubjectNew".to_string(),
-                payload: json!({ "subject": subject }),
-            }]),
+            Some(vec![Precondition::is_subject_new(subject)]),
         )
         .await;
 
@@ -208,7 +239,7 @@ async fn test_integration_commit_with_preconditions() {
 
 #[tokio::test]
 async fn test_integration_observe_events() {
-    let client = match create_integration_client() {
+    let client = match create_integration_client().await {
         Some(c) => c,
         None => {
             println!("Skipping integration test: GENESISDB_INTEGRATION_TESTS not set");
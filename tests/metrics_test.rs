@@ -0,0 +1,123 @@
+//! Tests for the client metrics
+//!
+//! Run with: cargo test --features metrics --test metrics_test
+
+#![cfg(feature = "metrics")]
+
+use genesisdb_io_client::metrics::*;
+use genesisdb_io_client::{Client, ClientConfig, CommitEvent, RetryPolicy};
+use metrics::{SharedString, Unit};
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use metrics_util::CompositeKey;
+use mockito::Server;
+use serde_json::json;
+use std::time::Duration;
+
+fn create_test_client(server_url: &str) -> Client {
+    Client::builder(ClientConfig {
+        api_url: server_url.to_string(),
+        api_version: "v1".to_string(),
+        auth_token: "test-token".to_string(),
+    })
+    .retry_policy(RetryPolicy {
+        initial_backoff: Duration::from_millis(1),
+        jitter: false,
+        ..Default::default()
+    })
+    .build()
+    .unwrap()
+}
+
+type Snapshot = Vec<(CompositeKey, Option<Unit>, Option<SharedString>, DebugValue)>;
+
+/// Value of the metric with the given name and labels
+fn value<'a>(snapshot: &'a Snapshot, name: &str, labels: &[(&str, &str)]) -> Option<&'a DebugValue> {
+    snapshot
+        .iter()
+        .find(|(key, _, _, _)| {
+            key.key().name() == name
+                && labels.iter().all(|(label, expected)| {
+                    key.key()
+                        .labels()
+                        .any(|l| l.key() == *label && l.value() == *expected)
+                })
+        })
+        .map(|(_, _, _, value)| value)
+}
+
+#[tokio::test]
+async fn test_requests_and_events_are_counted() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let _guard = metrics::set_default_local_recorder(&recorder);
+    let mut server = Server::new_async().await;
+
+    server
+        .mock("GET", "/api/v1/status/ping")
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/api/v1/status/ping")
+        .with_status(200)
+        .with_body("pong")
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/api/v1/commit")
+        .with_status(200)
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/api/v1/stream")
+        .with_status(200)
+        .with_body(concat!(
+            "{\"id\":\"a\",\"source\":\"s\",\"subject\":\"/user\",\"type\":\"t\"}\n",
+            "{\"id\":\"b\",\"source\":\"s\",\"subject\":\"/user\",\"type\":\"t\"}\n",
+            "not json\n",
+        ))
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    client.ping().await.unwrap();
+    let event = CommitEvent {
+        source: "test.source".to_string(),
+        subject: "/user".to_string(),
+        event_type: "created".to_string(),
+        data: json!({}),
+        ..Default::default()
+    };
+    client
+        .commit_events(vec![event.clone(), event], None)
+        .await
+        .unwrap();
+    assert!(client.stream_events("/user", None).await.is_err());
+
+    let snapshot = snapshotter.snapshot().into_vec();
+    assert_eq!(
+        value(&snapshot, REQUESTS_TOTAL, &[("operation", "ping"), ("status", "503")]),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        value(&snapshot, REQUESTS_TOTAL, &[("operation", "ping"), ("status", "200")]),
+        Some(&DebugValue::Counter(1))
+    );
+    assert!(matches!(
+        value(&snapshot, REQUEST_DURATION_SECONDS, &[("operation", "commit")]),
+        Some(DebugValue::Histogram(values)) if values.len() == 1
+    ));
+    assert!(matches!(
+        value(&snapshot, COMMIT_BATCH_SIZE, &[]),
+        Some(DebugValue::Histogram(values)) if values[0].into_inner() == 2.0
+    ));
+    assert_eq!(
+        value(&snapshot, EVENTS_RECEIVED_TOTAL, &[("operation", "stream")]),
+        Some(&DebugValue::Counter(2))
+    );
+    assert_eq!(
+        value(&snapshot, PARSE_ERRORS_TOTAL, &[("operation", "stream")]),
+        Some(&DebugValue::Counter(1))
+    );
+}
@@ -0,0 +1,245 @@
+//! Tests for projections running against the in-memory fake server
+//!
+//! Run with: cargo test --features testing --test projection_test
+
+#![cfg(feature = "testing")]
+
+use genesisdb_io_client::testing::FakeServer;
+use genesisdb_io_client::{
+    CheckpointStore, CloudEvent, CommitEvent, InMemoryCheckpointStore, Projection, ProjectionError,
+    ProjectionRunner,
+};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Records the ids of handled events, failing on events of type `poison`
+#[derive(Clone, Default)]
+struct Recorder {
+    handled: Arc<Mutex<Vec<String>>>,
+}
+
+impl Recorder {
+    fn handled(&self) -> Vec<String> {
+        self.handled.lock().unwrap().clone()
+    }
+}
+
+impl Projection for Recorder {
+    type Error = String;
+
+    fn name(&self) -> &str {
+        "recorder"
+    }
+
+    fn subject(&self) -> &str {
+        "/user"
+    }
+
+    async fn handle(&mut self, event: &CloudEvent) -> Result<(), String> {
+        if event.event_type == "poison" {
+            return Err("poisoned".to_string());
+        }
+        self.handled.lock().unwrap().push(event.id.clone());
+        Ok(())
+    }
+}
+
+/// In-memory store counting how often a checkpoint is saved
+#[derive(Clone, Default)]
+struct CountingStore {
+    inner: InMemoryCheckpointStore,
+    saves: Arc<Mutex<usize>>,
+}
+
+impl CountingStore {
+    fn saves(&self) -> usize {
+        *self.saves.lock().unwrap()
+    }
+}
+
+impl CheckpointStore for CountingStore {
+    async fn load(&self, projection: &str) -> genesisdb_io_client::Result<Option<String>> {
+        self.inner.load(projection).await
+    }
+
+    async fn save(&self, projection: &str, event_id: &str) -> genesisdb_io_client::Result<()> {
+        *self.saves.lock().unwrap() += 1;
+        self.inner.save(projection, event_id).await
+    }
+}
+
+fn event(subject: &str, event_type: &str) -> CommitEvent {
+    CommitEvent {
+        source: "io.genesisdb.test".to_string(),
+        subject: subject.to_string(),
+        event_type: event_type.to_string(),
+        data: json!({}),
+        ..Default::default()
+    }
+}
+
+fn ids(server: &FakeServer, subject: &str) -> Vec<String> {
+    server
+        .events()
+        .into_iter()
+        .filter(|event| event.subject.starts_with(subject))
+        .map(|event| event.id)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_catch_up_resumes_from_checkpoint() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+    let store = InMemoryCheckpointStore::new();
+
+    client
+        .commit_events(vec![event("/user/1", "created"), event("/order/1", "placed")], None)
+        .await
+        .unwrap();
+
+    let recorder = Recorder::default();
+    let mut runner = ProjectionRunner::new(client.clone(), recorder.clone(), store.clone());
+    let last = runner.catch_up().await.unwrap();
+    assert_eq!(recorder.handled(), ids(&server, "/user"));
+    assert_eq!(last, recorder.handled().last().cloned());
+    assert_eq!(store.load("recorder").await.unwrap(), last);
+
+    client
+        .commit_events(vec![event("/user/2", "created")], None)
+        .await
+        .unwrap();
+
+    // A new runner only handles the events after the checkpoint
+    let recorder = Recorder::default();
+    let mut runner = ProjectionRunner::new(client, recorder.clone(), store.clone());
+    runner.catch_up().await.unwrap();
+    assert_eq!(recorder.handled(), ids(&server, "/user/2"));
+}
+
+#[tokio::test]
+async fn test_catch_up_saves_checkpoint_in_intervals() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+    let store = CountingStore::default();
+
+    let events = (1..=5).map(|i| event(&format!("/user/{}", i), "created")).collect();
+    client.commit_events(events, None).await.unwrap();
+
+    let recorder = Recorder::default();
+    let mut runner =
+        ProjectionRunner::new(client.clone(), recorder.clone(), store.clone()).checkpoint_interval(2);
+    let last = runner.catch_up().await.unwrap();
+    assert_eq!(recorder.handled().len(), 5);
+    assert_eq!(store.saves(), 3);
+    assert_eq!(store.load("recorder").await.unwrap(), last);
+
+    // A failing event still saves the progress made before it
+    client
+        .commit_events(
+            vec![event("/user/6", "created"), event("/user/7", "created"), event("/user/6", "poison")],
+            None,
+        )
+        .await
+        .unwrap();
+    let mut runner = ProjectionRunner::new(client, Recorder::default(), store.clone());
+    assert!(matches!(runner.catch_up().await, Err(ProjectionError::Handler { .. })));
+    assert_eq!(store.saves(), 4);
+    let stored = ids(&server, "/user");
+    assert_eq!(store.load("recorder").await.unwrap().as_ref(), Some(&stored[6]));
+}
+
+#[tokio::test]
+async fn test_run_switches_to_live_events() {
+    let server = FakeServer::start().await.unwrap();
+    let client = server.client();
+    let store = InMemoryCheckpointStore::new();
+
+    client
+        .commit_events(vec![event("/user/1", "created")], None)
+        .await
+        .unwrap();
+
+    let recorder = Recorder::default();
+    let runner = ProjectionRunner::new(client.clone(), recorder.clone(), store.clone());
+    let handle = tokio::spawn(runner.run());
+
+    client
+        .commit_events(vec![event("/user/2", "created"), event("/user/1", "poison")], None)
+        .await
+        .unwrap();
+
+    let result = tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Every event is handled exactly once, and the checkpoint stays before the failed one
+    let stored = ids(&server, "/user");
+    assert_eq!(recorder.handled(), stored[..2]);
+    assert_eq!(store.load("recorder").await.unwrap().as_ref(), Some(&stored[1]));
+    match result {
+        Err(ProjectionError::Handler { event_id, source }) => {
+            assert_eq!(event_id, stored[2]);
+            assert_eq!(source, "poisoned");
+        }
+        other => panic!("expected handler error, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use genesisdb_io_client::rusqlite::{self, Transaction};
+    use genesisdb_io_client::{SqliteProjection, SqliteStore};
+
+    struct UserCount;
+
+    impl SqliteProjection for UserCount {
+        type Error = rusqlite::Error;
+
+        fn name(&self) -> &str {
+            "user-count"
+        }
+
+        fn subject(&self) -> &str {
+            "/user"
+        }
+
+        fn apply(&mut self, tx: &Transaction<'_>, _event: &CloudEvent) -> rusqlite::Result<()> {
+            tx.execute("UPDATE user_count SET count = count + 1", [])?;
+            Ok(())
+        }
+    }
+
+    fn count(store: &SqliteStore) -> i64 {
+        store.with_connection(|c| c.query_row("SELECT count FROM user_count", [], |row| row.get(0)).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_projection_applies_each_event_once() {
+        let server = FakeServer::start().await.unwrap();
+        let client = server.client();
+        let store = SqliteStore::open_in_memory().unwrap();
+        store.with_connection(|c| {
+            c.execute_batch("CREATE TABLE user_count (count INTEGER); INSERT INTO user_count VALUES (0);")
+                .unwrap()
+        });
+
+        client
+            .commit_events(vec![event("/user/1", "created"), event("/user/2", "created")], None)
+            .await
+            .unwrap();
+        store.runner(client.clone(), UserCount).catch_up().await.unwrap();
+        assert_eq!(count(&store), 2);
+
+        client
+            .commit_events(vec![event("/user/3", "created")], None)
+            .await
+            .unwrap();
+        let last = store.runner(client, UserCount).catch_up().await.unwrap();
+        assert_eq!(count(&store), 3);
+        assert_eq!(last, ids(&server, "/user/3").pop());
+    }
+}
@@ -0,0 +1,142 @@
+//! Tests for the tracing instrumentation
+//!
+//! Run with: cargo test --features tracing --test tracing_test
+
+#![cfg(feature = "tracing")]
+
+use genesisdb_io_client::{Client, ClientConfig, CommitEvent, RetryPolicy};
+use mockito::Server;
+use serde_json::json;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Collects everything the subscriber writes
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn output(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Captured {
+    type Writer = Captured;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+fn subscriber(captured: &Captured) -> impl tracing::Subscriber + Send + Sync {
+    tracing_subscriber::fmt()
+        .with_writer(captured.clone())
+        .with_max_level(tracing::Level::TRACE)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_ansi(false)
+        .finish()
+}
+
+fn create_test_client(server_url: &str) -> Client {
+    Client::builder(ClientConfig {
+        api_url: server_url.to_string(),
+        api_version: "v1".to_string(),
+        auth_token: "secret-token".to_string(),
+    })
+    .retry_policy(RetryPolicy {
+        initial_backoff: Duration::from_millis(1),
+        jitter: false,
+        ..Default::default()
+    })
+    .build()
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_operations_are_traced_without_credentials() {
+    let captured = Captured::default();
+    let _guard = tracing::subscriber::set_default(subscriber(&captured));
+    let mut server = Server::new_async().await;
+
+    server
+        .mock("POST", "/api/v1/commit")
+        .with_status(200)
+        .with_body(r#"[{"id":"a"}]"#)
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/api/v1/stream")
+        .with_status(200)
+        .with_body("{\"id\":\"a\",\"source\":\"s\",\"subject\":\"/user\",\"type\":\"t\"}\nnot json\n")
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    client
+        .commit_events(
+            vec![CommitEvent {
+                source: "test.source".to_string(),
+                subject: "/user".to_string(),
+                event_type: "created".to_string(),
+                data: json!({}),
+                ..Default::default()
+            }],
+            None,
+        )
+        .await
+        .unwrap();
+    assert!(client.stream_events("/user", None).await.is_err());
+
+    let output = captured.output();
+    assert!(output.contains("genesisdb.commit"), "{}", output);
+    assert!(output.contains("event_count=1"), "{}", output);
+    assert!(output.contains("status=200"), "{}", output);
+    assert!(output.contains("bytes_received=12"), "{}", output);
+    assert!(output.contains("genesisdb.stream"), "{}", output);
+    assert!(output.contains("subject=\"/user\""), "{}", output);
+    assert!(output.contains("failed to parse response line"), "{}", output);
+    assert!(output.contains("line=2"), "{}", output);
+    assert!(!output.contains("secret-token"), "{}", output);
+}
+
+#[tokio::test]
+async fn test_retries_are_traced() {
+    let captured = Captured::default();
+    let _guard = tracing::subscriber::set_default(subscriber(&captured));
+    let mut server = Server::new_async().await;
+
+    server
+        .mock("GET", "/api/v1/status/ping")
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/api/v1/status/ping")
+        .with_status(200)
+        .with_body("pong")
+        .create_async()
+        .await;
+
+    let client = create_test_client(&server.url());
+    assert_eq!(client.ping().await.unwrap(), "pong");
+
+    let output = captured.output();
+    assert!(output.contains("retrying request"), "{}", output);
+    assert!(output.contains("attempt=1"), "{}", output);
+    assert!(output.contains("genesisdb.ping"), "{}", output);
+    assert!(!output.contains("secret-token"), "{}", output);
+}